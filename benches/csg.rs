@@ -0,0 +1,80 @@
+#![feature(generic_const_exprs, test)]
+
+extern crate test;
+
+use isosurface_simplex::{
+    find_isosurface, SDFExpression, SDFVolume, SolverSettings, VolumetricFunc,
+};
+use nalgebra::Vector3;
+use test::{black_box, Bencher};
+
+// The same scene as the csg example.
+fn scene() -> SDFExpression {
+    let a = SDFExpression::sphere(Vector3::new(1.0, 1.0, 2.0), 2.0);
+    let b = SDFExpression::sphere(Vector3::new(3.0, 1.0, 2.0), 1.0);
+
+    SDFExpression::min(a, b)
+}
+
+fn volume() -> SDFVolume {
+    SDFVolume {
+        base: Vector3::new(-5.0, -5.0, -5.0),
+        size: Vector3::new(10.0, 10.0, 10.0),
+    }
+}
+
+fn sample_points() -> Vec<Vector3<f64>> {
+    (0..1000)
+        .map(|i| {
+            Vector3::new(i as f64 * 0.01, (i % 7) as f64, (i % 13) as f64 * 0.5)
+                - Vector3::repeat(2.5)
+        })
+        .collect()
+}
+
+fn bench_eval<F: VolumetricFunc>(b: &mut Bencher, func: &F) {
+    let points = sample_points();
+    b.iter(|| points.iter().map(|p| func.eval(black_box(p))).sum::<f64>());
+}
+
+fn bench_grad<F: VolumetricFunc>(b: &mut Bencher, func: &F) {
+    let points = sample_points();
+    b.iter(|| {
+        points
+            .iter()
+            .map(|p| func.grad(black_box(p)))
+            .sum::<Vector3<f64>>()
+    });
+}
+
+#[bench]
+fn expression_eval(b: &mut Bencher) {
+    bench_eval(b, &scene());
+}
+
+#[bench]
+fn tape_eval(b: &mut Bencher) {
+    bench_eval(b, &scene().compile());
+}
+
+#[bench]
+fn expression_grad(b: &mut Bencher) {
+    bench_grad(b, &scene());
+}
+
+#[bench]
+fn tape_grad(b: &mut Bencher) {
+    bench_grad(b, &scene().compile());
+}
+
+#[bench]
+fn expression_isosurface(b: &mut Bencher) {
+    let scene = scene();
+    b.iter(|| find_isosurface(&scene, &volume(), &SolverSettings::default()));
+}
+
+#[bench]
+fn tape_isosurface(b: &mut Bencher) {
+    let scene = scene().compile();
+    b.iter(|| find_isosurface(&scene, &volume(), &SolverSettings::default()));
+}
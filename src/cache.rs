@@ -1,11 +1,11 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use nalgebra::{SVector, Vector3};
 
 use crate::{
     partition::PartitionCoord,
     subspace::{R3Space, Subspace},
-    SDFVolume, VolumetricFunc,
+    Interval, SDFVolume, VolumetricFunc,
 };
 
 // An EvaluationCache is a cache of evaluations of an SDFExpression and its gradient.
@@ -15,16 +15,19 @@ pub(crate) struct EvaluationCache<'a> {
     func: &'a dyn VolumetricFunc,
 
     pub(crate) volume: &'a SDFVolume,
+    // The value of the function on the isosurface, positions below it are inside.
+    pub(crate) iso_level: f64,
 
     func_vals: BTreeMap<PartitionCoord<3>, f64>,
     grad_vals: BTreeMap<PartitionCoord<3>, Vector3<f64>>,
 }
 
 impl<'a> EvaluationCache<'a> {
-    pub(crate) fn new(func: &'a dyn VolumetricFunc, volume: &'a SDFVolume) -> Self {
+    pub(crate) fn new(func: &'a dyn VolumetricFunc, volume: &'a SDFVolume, iso_level: f64) -> Self {
         Self {
             func,
             volume,
+            iso_level,
             func_vals: BTreeMap::default(),
             grad_vals: BTreeMap::default(),
         }
@@ -37,6 +40,36 @@ impl<'a> EvaluationCache<'a> {
         })
     }
 
+    // Evaluates the function at every coord that isn't cached yet in a single batch.
+    pub(crate) fn prefetch<I>(&mut self, coords: I)
+    where
+        I: IntoIterator<Item = PartitionCoord<3>>,
+    {
+        let missing: BTreeSet<_> = coords
+            .into_iter()
+            .filter(|c| !self.func_vals.contains_key(c))
+            .collect();
+        let points: Vec<_> = missing.iter().map(|c| self.real_pos(c)).collect();
+
+        self.func_vals
+            .extend(missing.into_iter().zip(self.func.eval_many(&points)));
+    }
+
+    // Like prefetch, but for gradients.
+    pub(crate) fn prefetch_grad<I>(&mut self, coords: I)
+    where
+        I: IntoIterator<Item = PartitionCoord<3>>,
+    {
+        let missing: BTreeSet<_> = coords
+            .into_iter()
+            .filter(|c| !self.grad_vals.contains_key(c))
+            .collect();
+        let points: Vec<_> = missing.iter().map(|c| self.real_pos(c)).collect();
+
+        self.grad_vals
+            .extend(missing.into_iter().zip(self.func.grad_many(&points)));
+    }
+
     pub(crate) fn eval_vec<const N: usize, S>(
         &self,
         norm_pos: &SVector<f64, N>,
@@ -57,6 +90,25 @@ impl<'a> EvaluationCache<'a> {
         self.func.eval(real_pos)
     }
 
+    // Bounds the function over the segment at coord, if the function supports interval evaluation.
+    pub(crate) fn eval_interval(&self, coord: &PartitionCoord<3>) -> Option<Interval> {
+        let low = self
+            .volume
+            .real_pos(&coord.low_parents().norm_pos(), &R3Space());
+        let high = self
+            .volume
+            .real_pos(&coord.high_parents().norm_pos(), &R3Space());
+
+        self.func.eval_interval(&SDFVolume {
+            base: low,
+            size: high - low,
+        })
+    }
+
+    fn real_pos(&self, at: &PartitionCoord<3>) -> Vector3<f64> {
+        self.volume.real_pos(&at.norm_pos(), &R3Space())
+    }
+
     pub(crate) fn eval_grad(&mut self, at: &PartitionCoord<3>) -> Vector3<f64> {
         *self.grad_vals.entry(*at).or_insert_with(|| {
             self.func
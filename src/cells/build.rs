@@ -3,6 +3,7 @@ use std::{collections::BTreeMap, sync::Mutex};
 use crate::{
     cache::EvaluationCache,
     cells::CellCollection,
+    duals::subdivide_coord,
     partition::{PartitionCoord, PartitionTree},
     subspace::R3Space,
 };
@@ -16,13 +17,25 @@ use super::{
 // These sets of trees contain volume, face and edge cells respectively.
 // Trees are divided wherever a sign change occurs in the input function up to max_depth.
 // Trees of cells are divided min_depth times before sign changes are tested.
+// If interval_subdivision is set, trees are also divided wherever the function's interval bounds
+// over a cell contain 0, so features that don't cross a cell's corners aren't missed.
 pub(crate) fn build_cell_trees(
     cache: &mut EvaluationCache,
     min_depth: usize,
     max_depth: usize,
+    interval_subdivision: bool,
 ) -> (VolumeCellCollection, FaceCellCollection, EdgeCellCollection) {
-    let mut volume_tree =
-        volume_tree_with_min_depth(cache, min_depth, max_depth, PartitionCoord::default());
+    // The corners of every cell tested for sign changes at the first level are evaluated at once.
+    let first_cells = subdivide_coord(&PartitionCoord::default(), min_depth + 1);
+    cache.prefetch(first_cells.iter().flat_map(PartitionCoord::vertex_coords));
+
+    let mut volume_tree = volume_tree_with_min_depth(
+        cache,
+        min_depth,
+        max_depth,
+        interval_subdivision,
+        PartitionCoord::default(),
+    );
     volume_tree.prune();
     let mut volume_b_tree = BTreeMap::<R3Space, CellTree<3>>::default();
     volume_b_tree.insert(R3Space(), volume_tree);
@@ -36,9 +49,10 @@ pub(crate) fn build_cell_trees(
 
 fn sign_change(cache: &mut EvaluationCache, coord: PartitionCoord<3>) -> bool {
     let children = coord.vertex_coords();
-    let sign = cache.eval(&children[0]) > 0.0;
+    let level = cache.iso_level;
+    let sign = cache.eval(&children[0]) > level;
     for c in coord.vertex_coords() {
-        if (cache.eval(&c) > 0.0) != sign {
+        if (cache.eval(&c) > level) != sign {
             return true;
         }
     }
@@ -46,19 +60,32 @@ fn sign_change(cache: &mut EvaluationCache, coord: PartitionCoord<3>) -> bool {
     false
 }
 
+fn should_subdivide(
+    cache: &mut EvaluationCache,
+    coord: PartitionCoord<3>,
+    interval_subdivision: bool,
+) -> bool {
+    sign_change(cache, coord)
+        || (interval_subdivision
+            && cache
+                .eval_interval(&coord)
+                .is_some_and(|bounds| bounds.contains(cache.iso_level)))
+}
+
 fn volume_tree_with_min_depth(
     cache: &mut EvaluationCache,
     min_depth: usize,
     max_depth: usize,
+    interval_subdivision: bool,
     coord: PartitionCoord<3>,
 ) -> CellTree<3> {
     let children = match min_depth {
         0 => coord
             .child_coords()
-            .map(|c| volume_tree(cache, max_depth, c)),
-        _ => coord
-            .child_coords()
-            .map(|c| volume_tree_with_min_depth(cache, min_depth - 1, max_depth - 1, c)),
+            .map(|c| volume_tree(cache, max_depth, interval_subdivision, c)),
+        _ => coord.child_coords().map(|c| {
+            volume_tree_with_min_depth(cache, min_depth - 1, max_depth - 1, interval_subdivision, c)
+        }),
     };
 
     let out = PartitionTree::Node(Box::new(children));
@@ -69,17 +96,21 @@ fn volume_tree_with_min_depth(
 fn volume_tree(
     cache: &mut EvaluationCache,
     max_depth: usize,
+    interval_subdivision: bool,
     coord: PartitionCoord<3>,
 ) -> CellTree<3> {
-    let sign_change = sign_change(cache, coord);
-    match (max_depth, sign_change) {
+    let subdivide = should_subdivide(cache, coord, interval_subdivision);
+    match (max_depth, subdivide) {
         (0, true) => PartitionTree::Leaf(Mutex::new(Cell::<3>::default())),
         (0, false) => PartitionTree::None,
-        (_, true) => PartitionTree::Node(Box::new(
-            coord
-                .child_coords()
-                .map(|c| volume_tree(cache, max_depth - 1, c)),
-        )),
+        (_, true) => {
+            let children = coord.child_coords();
+            cache.prefetch(children.iter().flat_map(PartitionCoord::vertex_coords));
+
+            PartitionTree::Node(Box::new(
+                children.map(|c| volume_tree(cache, max_depth - 1, interval_subdivision, c)),
+            ))
+        }
         (_, false) => PartitionTree::None,
     }
 }
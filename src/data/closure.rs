@@ -0,0 +1,84 @@
+use nalgebra::Vector3;
+
+use super::VolumetricFunc;
+
+type GradFn = dyn Fn(&Vector3<f64>) -> Vector3<f64> + Send + Sync;
+
+// A ClosureFunc is a VolumetricFunc evaluating a closure.
+// If no gradient closure is given, gradients are approximated with central differences.
+pub struct ClosureFunc<F> {
+    func: F,
+    grad: Option<Box<GradFn>>,
+    step: f64,
+}
+
+impl<F> ClosureFunc<F>
+where
+    F: Fn(&Vector3<f64>) -> f64 + Send + Sync,
+{
+    pub fn new(func: F) -> Self {
+        Self {
+            func,
+            grad: None,
+            step: 1e-6,
+        }
+    }
+
+    // Uses grad to compute gradients instead of central differences.
+    pub fn with_grad<G>(mut self, grad: G) -> Self
+    where
+        G: Fn(&Vector3<f64>) -> Vector3<f64> + Send + Sync + 'static,
+    {
+        self.grad = Some(Box::new(grad));
+        self
+    }
+
+    // Sets the distance sampled on either side of a position when approximating gradients.
+    pub fn with_step(mut self, step: f64) -> Self {
+        self.step = step;
+        self
+    }
+}
+
+impl<F> VolumetricFunc for ClosureFunc<F>
+where
+    F: Fn(&Vector3<f64>) -> f64 + Send + Sync,
+{
+    fn eval(&self, at: &Vector3<f64>) -> f64 {
+        (self.func)(at)
+    }
+
+    fn grad(&self, at: &Vector3<f64>) -> Vector3<f64> {
+        if let Some(grad) = &self.grad {
+            return grad(at);
+        }
+
+        Vector3::from_fn(|i, _| {
+            let offset = Vector3::ith(i, self.step);
+            ((self.func)(&(at + offset)) - (self.func)(&(at - offset))) / (2.0 * self.step)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{Matrix3, Vector3};
+
+    use crate::{ClosureFunc, VolumetricFunc};
+
+    #[test]
+    fn central_differences_match_gradient() {
+        let sphere = |at: &Vector3<f64>| at.norm() - 1.0;
+        let exact = ClosureFunc::new(sphere).with_grad(|at| at.normalize());
+        let approx = ClosureFunc::new(sphere).with_step(1e-5);
+
+        let at = Vector3::new(0.8, 0.6, -0.3);
+        assert_eq!(exact.eval(&at), approx.eval(&at));
+        assert!((exact.grad(&at) - approx.grad(&at)).norm() < 1e-8);
+
+        // The hessian of a distance to a point projects onto the plane normal to the gradient.
+        let n = at.normalize();
+        let expected = (Matrix3::identity() - n * n.transpose()) / at.norm();
+        assert!((exact.hessian(&at) - expected).norm() < 1e-6);
+    }
+}
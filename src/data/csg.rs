@@ -0,0 +1,174 @@
+use nalgebra::Vector3;
+
+use super::{Interval, SDFVolume, VolumetricFunc};
+
+// The boolean operation a CSG applies to its operands.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CSGOp {
+    Union,
+    Intersection,
+    // The first operand with the second removed from it.
+    Difference,
+}
+
+// A CSG combines two VolumetricFuncs of any kind, like a MeshSDF and an SDFExpression, with a
+// boolean operation. A blend radius rounds off the creases where they meet, matching
+// SDFExpression::smooth_min and smooth_max.
+// Gradients and interval bounds are built from those of the operands.
+pub struct CSG<A, B> {
+    a: A,
+    b: B,
+    op: CSGOp,
+    radius: f64,
+}
+
+impl<A, B> CSG<A, B>
+where
+    A: VolumetricFunc,
+    B: VolumetricFunc,
+{
+    pub fn new(op: CSGOp, a: A, b: B) -> Self {
+        Self {
+            a,
+            b,
+            op,
+            radius: 0.0,
+        }
+    }
+
+    pub fn union(a: A, b: B) -> Self {
+        Self::new(CSGOp::Union, a, b)
+    }
+
+    pub fn intersection(a: A, b: B) -> Self {
+        Self::new(CSGOp::Intersection, a, b)
+    }
+
+    pub fn difference(a: A, b: B) -> Self {
+        Self::new(CSGOp::Difference, a, b)
+    }
+
+    // Blends the operands where they're within radius of each other.
+    pub fn with_blend(mut self, radius: f64) -> Self {
+        assert!(radius >= 0.0, "Blend radii can't be negative.");
+        self.radius = radius;
+        self
+    }
+
+    pub fn operands(&self) -> (&A, &B) {
+        (&self.a, &self.b)
+    }
+
+    // Every operation is a smooth minimum of the operands with their signs changed,
+    // these are the signs of a, b and the result.
+    fn signs(&self) -> [f64; 3] {
+        match self.op {
+            CSGOp::Union => [1.0, 1.0, 1.0],
+            CSGOp::Intersection => [-1.0, -1.0, -1.0],
+            CSGOp::Difference => [-1.0, 1.0, -1.0],
+        }
+    }
+}
+
+// The polynomial smooth minimum of two values and their gradients.
+fn smooth_min(
+    (a, a_grad): (f64, Vector3<f64>),
+    (b, b_grad): (f64, Vector3<f64>),
+    radius: f64,
+) -> (f64, Vector3<f64>) {
+    let (min, min_grad) = if a > b { (b, b_grad) } else { (a, a_grad) };
+    let h = radius - (a - b).abs();
+    if h <= 0.0 {
+        return (min, min_grad);
+    }
+
+    // min - h^2 / (4 radius), where h = radius - |a - b|.
+    let sign = if a > b { 1.0 } else { -1.0 };
+    let h_grad = (a_grad - b_grad) * -sign;
+    (
+        min - h * h * 0.25 / radius,
+        min_grad - h_grad * (0.5 * h / radius),
+    )
+}
+
+impl<A, B> VolumetricFunc for CSG<A, B>
+where
+    A: VolumetricFunc,
+    B: VolumetricFunc,
+{
+    fn eval(&self, at: &Vector3<f64>) -> f64 {
+        let [sa, sb, s] = self.signs();
+        let zero = Vector3::zeros();
+        let (val, _) = smooth_min(
+            (sa * self.a.eval(at), zero),
+            (sb * self.b.eval(at), zero),
+            self.radius,
+        );
+        s * val
+    }
+
+    fn grad(&self, at: &Vector3<f64>) -> Vector3<f64> {
+        self.eval_with_grad(at).1
+    }
+
+    fn eval_with_grad(&self, at: &Vector3<f64>) -> (f64, Vector3<f64>) {
+        let [sa, sb, s] = self.signs();
+        let (a, a_grad) = self.a.eval_with_grad(at);
+        let (b, b_grad) = self.b.eval_with_grad(at);
+        let (val, grad) = smooth_min((sa * a, a_grad * sa), (sb * b, b_grad * sb), self.radius);
+        (s * val, grad * s)
+    }
+
+    // The blend lowers the minimum by at most radius / 4.
+    fn eval_interval(&self, volume: &SDFVolume) -> Option<Interval> {
+        let [sa, sb, s] = self.signs();
+        let a = Interval::point(sa) * self.a.eval_interval(volume)?;
+        let b = Interval::point(sb) * self.b.eval_interval(volume)?;
+        let min = Interval::new(a.lo.min(b.lo) - 0.25 * self.radius, a.hi.min(b.hi));
+        Some(Interval::point(s) * min)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+
+    use crate::{SDFExpression, SDFVolume, VolumetricFunc, CSG};
+
+    #[test]
+    fn csg_matches_expressions() {
+        let sphere = SDFExpression::sphere(Vector3::new(0.5, 0.0, 0.0), 1.0);
+        let cuboid = SDFExpression::cuboid(Vector3::zeros(), Vector3::new(0.8, 0.6, 0.4));
+        let volume = SDFVolume {
+            base: Vector3::new(-1.0, -1.0, -1.0),
+            size: Vector3::new(2.0, 2.0, 2.0),
+        };
+
+        let (s, c) = (sphere.clone(), cuboid.clone());
+        let cases = [
+            (
+                CSG::union(s.clone(), c.clone()),
+                SDFExpression::min(s.clone(), c.clone()),
+            ),
+            (
+                CSG::intersection(s.clone(), c.clone()).with_blend(0.3),
+                SDFExpression::smooth_max(s.clone(), c.clone(), 0.3),
+            ),
+            (
+                CSG::difference(s.clone(), c.clone()).with_blend(0.2),
+                SDFExpression::smooth_subtract(s, c, 0.2),
+            ),
+        ];
+
+        for (csg, expr) in cases {
+            let interval = csg.eval_interval(&volume).unwrap();
+            for i in 0..50 {
+                let t = i as f64 * 0.04 - 1.0;
+                let at = Vector3::new(t, 0.5 * (2.0 * t).sin(), 0.3 * t);
+                assert!((csg.eval(&at) - expr.eval(&at)).abs() < 1e-12, "{}", at);
+                assert!((csg.grad(&at) - expr.grad(&at)).norm() < 1e-12, "{}", at);
+                assert!(interval.contains(csg.eval(&at)));
+            }
+        }
+    }
+}
@@ -0,0 +1,305 @@
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use nalgebra::Vector3;
+
+use super::VolumetricFunc;
+
+// A Scalar is a number type functions can be evaluated with.
+// Writing a function generically over Scalar allows it to be evaluated with f64 for values
+// or with Dual for values and gradients.
+pub trait Scalar:
+    Copy
+    + From<f64>
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+{
+    // The plain value of this number, used for comparisons.
+    fn value(&self) -> f64;
+
+    fn sqrt(self) -> Self;
+    fn abs(self) -> Self;
+    fn recip(self) -> Self;
+    fn pow(self, exponent: Self) -> Self;
+    fn exp(self) -> Self;
+    fn ln(self) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    fn floor(self) -> Self;
+    fn atan2(self, x: Self) -> Self;
+
+    // A function of three arguments given its value and a function returning its partial
+    // derivative with respect to each argument.
+    fn from_partials<F: Fn(usize) -> f64>(val: f64, partials: F, args: &[Self; 3]) -> Self;
+
+    fn max(self, other: Self) -> Self {
+        if self.value() > other.value() {
+            self
+        } else {
+            other
+        }
+    }
+
+    fn min(self, other: Self) -> Self {
+        if self.value() > other.value() {
+            other
+        } else {
+            self
+        }
+    }
+}
+
+impl Scalar for f64 {
+    fn value(&self) -> f64 {
+        *self
+    }
+
+    fn sqrt(self) -> Self {
+        f64::sqrt(self)
+    }
+
+    fn abs(self) -> Self {
+        f64::abs(self)
+    }
+
+    fn recip(self) -> Self {
+        f64::recip(self)
+    }
+
+    fn pow(self, exponent: Self) -> Self {
+        self.powf(exponent)
+    }
+
+    fn exp(self) -> Self {
+        f64::exp(self)
+    }
+
+    fn ln(self) -> Self {
+        f64::ln(self)
+    }
+
+    fn sin(self) -> Self {
+        f64::sin(self)
+    }
+
+    fn cos(self) -> Self {
+        f64::cos(self)
+    }
+
+    fn floor(self) -> Self {
+        f64::floor(self)
+    }
+
+    fn atan2(self, x: Self) -> Self {
+        f64::atan2(self, x)
+    }
+
+    fn from_partials<F: Fn(usize) -> f64>(val: f64, _partials: F, _args: &[Self; 3]) -> Self {
+        val
+    }
+}
+
+// A Dual is a value along with its gradient with respect to position.
+// Evaluating a function with Duals computes its exact gradient in the same pass as its value.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Dual {
+    pub val: f64,
+    pub grad: Vector3<f64>,
+}
+
+impl Dual {
+    pub fn new(val: f64, grad: Vector3<f64>) -> Self {
+        Self { val, grad }
+    }
+
+    // The x, y and z coordinates of a position as Duals, each with a gradient along its own axis.
+    pub fn position(at: &Vector3<f64>) -> [Self; 3] {
+        [0, 1, 2].map(|i| Self::new(at[i], Vector3::ith(i, 1.0)))
+    }
+
+    // Applies a function with value val and derivative deriv at self.val.
+    fn chain(self, val: f64, deriv: f64) -> Self {
+        Self::new(val, self.grad * deriv)
+    }
+}
+
+impl Scalar for Dual {
+    fn value(&self) -> f64 {
+        self.val
+    }
+
+    fn sqrt(self) -> Self {
+        let val = self.val.sqrt();
+        self.chain(val, 0.5 / val)
+    }
+
+    fn abs(self) -> Self {
+        self.chain(self.val.abs(), if self.val > 0.0 { 1.0 } else { -1.0 })
+    }
+
+    fn recip(self) -> Self {
+        let val = self.val.recip();
+        self.chain(val, -val * val)
+    }
+
+    // d a^b = b a^(b - 1) a' + a^b ln(a) b'
+    fn pow(self, exponent: Self) -> Self {
+        let val = self.val.powf(exponent.val);
+        let mut grad = self.grad * (exponent.val * self.val.powf(exponent.val - 1.0));
+
+        // Skipped for constant exponents, where ln(a) may be NaN.
+        if exponent.grad != Vector3::zeros() {
+            grad += exponent.grad * (val * self.val.ln());
+        }
+
+        Self::new(val, grad)
+    }
+
+    fn exp(self) -> Self {
+        let val = self.val.exp();
+        self.chain(val, val)
+    }
+
+    fn ln(self) -> Self {
+        self.chain(self.val.ln(), self.val.recip())
+    }
+
+    fn sin(self) -> Self {
+        self.chain(self.val.sin(), self.val.cos())
+    }
+
+    fn cos(self) -> Self {
+        self.chain(self.val.cos(), -self.val.sin())
+    }
+
+    fn floor(self) -> Self {
+        self.val.floor().into()
+    }
+
+    // d atan2(a, b) = (b a' - a b') / (a^2 + b^2)
+    fn atan2(self, x: Self) -> Self {
+        let len_sq = self.val * self.val + x.val * x.val;
+        Self::new(
+            self.val.atan2(x.val),
+            (self.grad * x.val - x.grad * self.val) / len_sq,
+        )
+    }
+
+    fn from_partials<F: Fn(usize) -> f64>(val: f64, partials: F, args: &[Self; 3]) -> Self {
+        let grad = (0..3).map(|i| args[i].grad * partials(i)).sum();
+        Self::new(val, grad)
+    }
+}
+
+impl From<f64> for Dual {
+    fn from(value: f64) -> Self {
+        Self::new(value, Vector3::zeros())
+    }
+}
+
+impl Add for Dual {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(self.val + rhs.val, self.grad + rhs.grad)
+    }
+}
+
+impl Sub for Dual {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::new(self.val - rhs.val, self.grad - rhs.grad)
+    }
+}
+
+impl Neg for Dual {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self::new(-self.val, -self.grad)
+    }
+}
+
+impl Mul for Dual {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self::new(
+            self.val * rhs.val,
+            self.grad * rhs.val + rhs.grad * self.val,
+        )
+    }
+}
+
+impl Div for Dual {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        let val = self.val / rhs.val;
+        Self::new(val, (self.grad - rhs.grad * val) / rhs.val)
+    }
+}
+
+// A ScalarFunc is a function of position written generically over Scalar.
+// Closures can't be generic, so functions are written as a type implementing this trait.
+pub trait ScalarFunc: Send + Sync {
+    fn eval_scalar<S: Scalar>(&self, at: &[S; 3]) -> S;
+}
+
+// AutoDiff is a VolumetricFunc for a ScalarFunc, computing gradients with Duals.
+#[derive(Clone)]
+pub struct AutoDiff<F>(pub F);
+
+impl<F> VolumetricFunc for AutoDiff<F>
+where
+    F: ScalarFunc,
+{
+    fn eval(&self, at: &Vector3<f64>) -> f64 {
+        self.0.eval_scalar(&(*at).into())
+    }
+
+    fn grad(&self, at: &Vector3<f64>) -> Vector3<f64> {
+        self.0.eval_scalar(&Dual::position(at)).grad
+    }
+
+    fn eval_with_grad(&self, at: &Vector3<f64>) -> (f64, Vector3<f64>) {
+        let Dual { val, grad } = self.0.eval_scalar(&Dual::position(at));
+        (val, grad)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+
+    use crate::{AutoDiff, Scalar, ScalarFunc, VolumetricFunc};
+
+    struct Twisted;
+
+    impl ScalarFunc for Twisted {
+        fn eval_scalar<S: Scalar>(&self, [x, y, z]: &[S; 3]) -> S {
+            let angle = *z * 0.5.into();
+            let u = *x * angle.cos() - *y * angle.sin();
+            let v = (*x * *x + *y * *y + 1.0.into()).ln();
+            (u * u + v * v).sqrt() - 1.0.into() + y.atan2(*x).max(z.abs().pow(1.5.into()))
+        }
+    }
+
+    #[test]
+    fn dual_gradients_match_finite_differences() {
+        let func = AutoDiff(Twisted);
+        let at = Vector3::new(0.8, 0.6, -0.3);
+        let h = 1e-6;
+
+        let (val, grad) = func.eval_with_grad(&at);
+        assert_eq!(val, func.eval(&at));
+        for i in 0..3 {
+            let offset = Vector3::ith(i, h);
+            let fd = (func.eval(&(at + offset)) - func.eval(&(at - offset))) / (2.0 * h);
+            assert!((grad[i] - fd).abs() < 1e-4, "{} != {}", grad[i], fd);
+        }
+    }
+}
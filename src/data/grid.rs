@@ -0,0 +1,297 @@
+use nalgebra::Vector3;
+
+use super::{Interval, SDFVolume, VolumetricFunc};
+
+// How a GridVolume interpolates between samples.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Interpolation {
+    Trilinear,
+    // Catmull-Rom splines along each axis, which have continuous gradients.
+    Tricubic,
+}
+
+// A GridVolume is a VolumetricFunc interpolating a dense 3D grid of samples,
+// such as a CT scan or the output of a simulation.
+// Positions outside the grid take the value of the nearest point on its boundary.
+#[derive(Clone)]
+pub struct GridVolume {
+    values: Vec<f64>,
+    dims: [usize; 3],
+    origin: Vector3<f64>,
+    spacing: Vector3<f64>,
+    interpolation: Interpolation,
+}
+
+// A sample's index and its weight and the weight's derivative along one axis.
+type Tap = (usize, f64, f64);
+
+impl GridVolume {
+    // Creates a grid from values stored with x varying fastest, then y, then z.
+    // The sample at index [i, j, k] is at origin + spacing * [i, j, k].
+    // Negative spacings, common in medical volumes, place later samples at lower positions.
+    pub fn new(
+        values: Vec<f64>,
+        dims: [usize; 3],
+        origin: Vector3<f64>,
+        spacing: Vector3<f64>,
+    ) -> Self {
+        assert_eq!(
+            values.len(),
+            dims.iter().product::<usize>(),
+            "Grid values don't match its dimensions."
+        );
+        assert!(
+            dims.iter().all(|n| *n > 0),
+            "Grids need at least one sample."
+        );
+
+        Self {
+            values,
+            dims,
+            origin,
+            spacing,
+            interpolation: Interpolation::Trilinear,
+        }
+    }
+
+    // Samples func at every point of a grid.
+    pub fn from_fn<F>(
+        dims: [usize; 3],
+        origin: Vector3<f64>,
+        spacing: Vector3<f64>,
+        func: F,
+    ) -> Self
+    where
+        F: Fn(&Vector3<f64>) -> f64,
+    {
+        let mut values = Vec::with_capacity(dims.iter().product());
+        for k in 0..dims[2] {
+            for j in 0..dims[1] {
+                for i in 0..dims[0] {
+                    let index = Vector3::new(i as f64, j as f64, k as f64);
+                    values.push(func(&(origin + spacing.component_mul(&index))));
+                }
+            }
+        }
+
+        Self::new(values, dims, origin, spacing)
+    }
+
+    pub fn with_interpolation(mut self, interpolation: Interpolation) -> Self {
+        self.interpolation = interpolation;
+        self
+    }
+
+    pub fn dims(&self) -> [usize; 3] {
+        self.dims
+    }
+
+    pub fn origin(&self) -> Vector3<f64> {
+        self.origin
+    }
+
+    pub fn spacing(&self) -> Vector3<f64> {
+        self.spacing
+    }
+
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+
+    // The sample at a grid index.
+    pub fn get(&self, index: [usize; 3]) -> f64 {
+        let [nx, ny, _] = self.dims;
+        self.values[index[0] + nx * (index[1] + ny * index[2])]
+    }
+
+    // The volume covered by the samples, which can be passed to find_isosurface.
+    pub fn bounds(&self) -> SDFVolume {
+        let extent = Vector3::from_fn(|i, _| (self.dims[i] - 1) as f64);
+        let end = self.origin + self.spacing.component_mul(&extent);
+        SDFVolume {
+            base: self.origin.inf(&end),
+            size: (end - self.origin).abs(),
+        }
+    }
+
+    // The samples and weights used along an axis for position g in grid units.
+    fn taps<const T: usize>(&self, axis: usize, g: f64) -> [Tap; T] {
+        let last = self.dims[axis] - 1;
+        let clamped = g.clamp(0.0, last as f64);
+        let cell = (clamped.floor() as usize).min(last.saturating_sub(1));
+        let t = clamped - cell as f64;
+        // The value doesn't change along axes where the position is outside the grid.
+        let d = if clamped == g { 1.0 } else { 0.0 };
+
+        let index = |offset: isize| (cell as isize + offset).clamp(0, last as isize) as usize;
+        let (t2, t3) = (t * t, t * t * t);
+        let weights = match T {
+            2 => [
+                (0, 1.0 - t, -1.0),
+                (1, t, 1.0),
+                (0, 0.0, 0.0),
+                (0, 0.0, 0.0),
+            ],
+            _ => [
+                (
+                    -1,
+                    0.5 * (-t3 + 2.0 * t2 - t),
+                    0.5 * (-3.0 * t2 + 4.0 * t - 1.0),
+                ),
+                (
+                    0,
+                    0.5 * (3.0 * t3 - 5.0 * t2 + 2.0),
+                    0.5 * (9.0 * t2 - 10.0 * t),
+                ),
+                (
+                    1,
+                    0.5 * (-3.0 * t3 + 4.0 * t2 + t),
+                    0.5 * (-9.0 * t2 + 8.0 * t + 1.0),
+                ),
+                (2, 0.5 * (t3 - t2), 0.5 * (3.0 * t2 - 2.0 * t)),
+            ],
+        };
+
+        std::array::from_fn(|i| {
+            let (offset, w, dw) = weights[i];
+            (index(offset), w, dw * d / self.spacing[axis])
+        })
+    }
+
+    fn interpolate<const T: usize>(&self, at: &Vector3<f64>) -> (f64, Vector3<f64>) {
+        let g = (at - self.origin).component_div(&self.spacing);
+        let [xs, ys, zs]: [[Tap; T]; 3] = [0, 1, 2].map(|axis| self.taps(axis, g[axis]));
+
+        let mut val = 0.0;
+        let mut grad = Vector3::zeros();
+        for (k, wz, dz) in zs {
+            for (j, wy, dy) in ys {
+                for (i, wx, dx) in xs {
+                    let v = self.get([i, j, k]);
+                    val += v * wx * wy * wz;
+                    grad += v * Vector3::new(dx * wy * wz, wx * dy * wz, wx * wy * dz);
+                }
+            }
+        }
+
+        (val, grad)
+    }
+}
+
+impl VolumetricFunc for GridVolume {
+    fn eval(&self, at: &Vector3<f64>) -> f64 {
+        self.eval_with_grad(at).0
+    }
+
+    fn grad(&self, at: &Vector3<f64>) -> Vector3<f64> {
+        self.eval_with_grad(at).1
+    }
+
+    fn eval_with_grad(&self, at: &Vector3<f64>) -> (f64, Vector3<f64>) {
+        match self.interpolation {
+            Interpolation::Trilinear => self.interpolate::<2>(at),
+            Interpolation::Tricubic => self.interpolate::<4>(at),
+        }
+    }
+
+    // Bounds come from the range of the samples the region's values are interpolated from.
+    fn eval_interval(&self, volume: &SDFVolume) -> Option<Interval> {
+        let (reach, overshoot) = match self.interpolation {
+            Interpolation::Trilinear => (0, 1.0),
+            // Catmull-Rom weights can be negative, their absolute values sum to at most 1.25
+            // along each axis.
+            Interpolation::Tricubic => (1, 1.25f64.powi(3)),
+        };
+
+        let range = [0, 1, 2].map(|axis| {
+            let last = self.dims[axis] as f64 - 1.0;
+            let to_grid = |x: f64| ((x - self.origin[axis]) / self.spacing[axis]).clamp(0.0, last);
+            // With a negative spacing the end of the region has the lower grid position.
+            let (a, b) = (
+                to_grid(volume.base[axis]),
+                to_grid(volume.base[axis] + volume.size[axis]),
+            );
+            let lo = a.min(b).floor() as usize;
+            let hi = a.max(b).ceil() as usize;
+            lo.saturating_sub(reach)..(hi + reach).min(self.dims[axis] - 1) + 1
+        });
+
+        let mut samples = Interval::new(f64::INFINITY, f64::NEG_INFINITY);
+        for k in range[2].clone() {
+            for j in range[1].clone() {
+                for i in range[0].clone() {
+                    samples = samples.hull(&Interval::point(self.get([i, j, k])));
+                }
+            }
+        }
+
+        let mid = 0.5 * (samples.lo + samples.hi);
+        let half = 0.5 * (samples.hi - samples.lo) * overshoot;
+        Some(Interval::new(mid - half, mid + half))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+
+    use crate::{find_isosurface, GridVolume, Interpolation, SolverSettings, VolumetricFunc};
+
+    #[test]
+    fn grids_reproduce_sampled_functions() {
+        let origin = Vector3::new(-2.0, -2.0, -2.0);
+        let spacing = Vector3::new(0.25, 0.2, 0.25);
+        let linear = |at: &Vector3<f64>| at.dot(&Vector3::new(1.0, -2.0, 0.5)) + 3.0;
+        let quadratic = |at: &Vector3<f64>| at.norm_squared() - 1.0;
+
+        let linear_grid = GridVolume::from_fn([17, 21, 17], origin, spacing, linear);
+        let quadratic_grid = GridVolume::from_fn([17, 21, 17], origin, spacing, quadratic)
+            .with_interpolation(Interpolation::Tricubic);
+
+        let at = Vector3::new(0.33, -0.71, 1.07);
+        assert!((linear_grid.eval(&at) - linear(&at)).abs() < 1e-12);
+        assert!((linear_grid.grad(&at) - Vector3::new(1.0, -2.0, 0.5)).norm() < 1e-12);
+        assert!((quadratic_grid.eval(&at) - quadratic(&at)).abs() < 1e-12);
+        assert!((quadratic_grid.grad(&at) - 2.0 * at).norm() < 1e-12);
+
+        let bounds = quadratic_grid.bounds();
+        assert_eq!(bounds.size, Vector3::new(4.0, 4.0, 4.0));
+        let interval = quadratic_grid.eval_interval(&bounds).unwrap();
+        assert!(interval.contains(-1.0) && interval.contains(11.0));
+
+        // The grid can be meshed directly.
+        let settings = SolverSettings {
+            min_octree_depth: 2,
+            max_octree_depth: 3,
+            ..Default::default()
+        };
+        let mesh = find_isosurface(&quadratic_grid, &bounds, &settings);
+        assert!(!mesh.0.is_empty());
+        for vert in mesh.0 {
+            assert!((vert.norm() - 1.0).abs() < 0.05);
+        }
+    }
+
+    #[test]
+    fn negative_spacings_mirror_the_grid() {
+        // The samples run from x = 2 down to x = -2.
+        let linear = |at: &Vector3<f64>| at.dot(&Vector3::new(1.0, -2.0, 0.5)) + 3.0;
+        let grid = GridVolume::from_fn(
+            [9, 5, 5],
+            Vector3::new(2.0, -1.0, 0.0),
+            Vector3::new(-0.5, 0.5, 0.5),
+            linear,
+        );
+
+        let bounds = grid.bounds();
+        assert_eq!(bounds.base, Vector3::new(-2.0, -1.0, 0.0));
+        assert_eq!(bounds.size, Vector3::new(4.0, 2.0, 2.0));
+
+        let at = Vector3::new(-1.3, 0.4, 1.1);
+        assert!((grid.eval(&at) - linear(&at)).abs() < 1e-12);
+        assert!((grid.grad(&at) - Vector3::new(1.0, -2.0, 0.5)).norm() < 1e-12);
+
+        let interval = grid.eval_interval(&bounds).unwrap();
+        assert_eq!((interval.lo, interval.hi), (-1.0, 8.0));
+    }
+}
@@ -0,0 +1,279 @@
+use std::{
+    f64::consts::{FRAC_PI_2, PI, TAU},
+    ops::{Add, Mul, Neg, Sub},
+};
+
+// An Interval is a closed range of values, used to bound a function over a region.
+// Operations on intervals always return an interval containing every possible result,
+// but may be wider than necessary.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Interval {
+    pub lo: f64,
+    pub hi: f64,
+}
+
+impl Interval {
+    pub fn new(lo: f64, hi: f64) -> Self {
+        Self { lo, hi }
+    }
+
+    pub fn point(val: f64) -> Self {
+        Self::new(val, val)
+    }
+
+    pub fn entire() -> Self {
+        Self::new(f64::NEG_INFINITY, f64::INFINITY)
+    }
+
+    pub fn contains(&self, val: f64) -> bool {
+        self.lo <= val && val <= self.hi
+    }
+
+    // The smallest interval containing both self and other.
+    pub fn hull(&self, other: &Self) -> Self {
+        Self::new(self.lo.min(other.lo), self.hi.max(other.hi))
+    }
+
+    // The largest absolute value in this interval.
+    pub fn mag(&self) -> f64 {
+        self.lo.abs().max(self.hi.abs())
+    }
+
+    // The interval version of choosing true_val where left > right and false_val elsewhere.
+    pub(crate) fn select(left: Self, right: Self, true_val: Self, false_val: Self) -> Self {
+        if left.lo > right.hi {
+            true_val
+        } else if left.hi <= right.lo {
+            false_val
+        } else {
+            true_val.hull(&false_val)
+        }
+    }
+
+    // Returns the smallest interval containing every value in vals.
+    // A NaN value could be anything, so it gives the entire interval.
+    fn from_values<I: IntoIterator<Item = f64>>(vals: I) -> Self {
+        let mut bounds = Self::new(f64::INFINITY, f64::NEG_INFINITY);
+        for v in vals {
+            if v.is_nan() {
+                return Self::entire();
+            }
+            bounds = Self::new(bounds.lo.min(v), bounds.hi.max(v));
+        }
+        bounds
+    }
+
+    // The interval of self * self, which unlike the product of two intervals is never negative.
+    pub fn square(self) -> Self {
+        let a = self.abs();
+        Self::new(a.lo * a.lo, a.hi * a.hi)
+    }
+
+    pub fn sqrt(self) -> Self {
+        Self::new(self.lo.max(0.0).sqrt(), self.hi.max(0.0).sqrt())
+    }
+
+    pub fn abs(self) -> Self {
+        if self.lo >= 0.0 {
+            self
+        } else if self.hi <= 0.0 {
+            -self
+        } else {
+            Self::new(0.0, self.mag())
+        }
+    }
+
+    pub fn recip(self) -> Self {
+        if self.contains(0.0) {
+            Self::entire()
+        } else {
+            Self::new(self.hi.recip(), self.lo.recip())
+        }
+    }
+
+    pub fn exp(self) -> Self {
+        Self::new(self.lo.exp(), self.hi.exp())
+    }
+
+    pub fn ln(self) -> Self {
+        Self::new(self.lo.max(0.0).ln(), self.hi.max(0.0).ln())
+    }
+
+    pub fn sin(self) -> Self {
+        if self.hi - self.lo >= TAU {
+            return Self::new(-1.0, 1.0);
+        }
+
+        // Include the peaks of sin that fall inside the interval.
+        let contains_peak = |peak: f64| {
+            let k = ((self.lo - peak) / TAU).ceil();
+            peak + k * TAU <= self.hi
+        };
+        let lo = if contains_peak(-FRAC_PI_2) {
+            -1.0
+        } else {
+            self.lo.sin().min(self.hi.sin())
+        };
+        let hi = if contains_peak(FRAC_PI_2) {
+            1.0
+        } else {
+            self.lo.sin().max(self.hi.sin())
+        };
+        Self::new(lo, hi)
+    }
+
+    pub fn cos(self) -> Self {
+        (self + Self::point(FRAC_PI_2)).sin()
+    }
+
+    pub fn floor(self) -> Self {
+        Self::new(self.lo.floor(), self.hi.floor())
+    }
+
+    pub fn pow(self, exponent: Self) -> Self {
+        if exponent.lo == exponent.hi {
+            let e = exponent.lo;
+            if e == 0.0 {
+                Self::point(1.0)
+            } else if self.lo >= 0.0 {
+                Self::from_values([self.lo.powf(e), self.hi.powf(e)])
+            } else if e.fract() == 0.0 {
+                // Integer powers have their only turning point or pole at 0.
+                match (self.contains(0.0), e > 0.0) {
+                    (true, true) => Self::from_values([self.lo.powf(e), self.hi.powf(e), 0.0]),
+                    (true, false) => Self::entire(),
+                    (false, _) => Self::from_values([self.lo.powf(e), self.hi.powf(e)]),
+                }
+            } else {
+                Self::entire()
+            }
+        } else if self.lo > 0.0 {
+            (exponent * self.ln()).exp()
+        } else {
+            Self::entire()
+        }
+    }
+
+    // The interval of atan2(self, x), self being the y coordinate.
+    pub fn atan2(self, x: Self) -> Self {
+        // Across the negative x axis atan2 jumps from PI to -PI.
+        if x.lo < 0.0 && self.contains(0.0) {
+            return Self::new(-PI, PI);
+        }
+
+        // Otherwise the angles of a box are bounded by the angles of its corners.
+        Self::from_values([
+            self.lo.atan2(x.lo),
+            self.lo.atan2(x.hi),
+            self.hi.atan2(x.lo),
+            self.hi.atan2(x.hi),
+        ])
+    }
+}
+
+impl Add for Interval {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(self.lo + rhs.lo, self.hi + rhs.hi)
+    }
+}
+
+impl Sub for Interval {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self + (-rhs)
+    }
+}
+
+impl Neg for Interval {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self::new(-self.hi, -self.lo)
+    }
+}
+
+impl Mul for Interval {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        // The bounds of 0 times an unbounded interval are 0, not the NaN of 0 * inf.
+        let mul = |a: f64, b: f64| if a == 0.0 || b == 0.0 { 0.0 } else { a * b };
+        Self::from_values([
+            mul(self.lo, rhs.lo),
+            mul(self.lo, rhs.hi),
+            mul(self.hi, rhs.lo),
+            mul(self.hi, rhs.hi),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+
+    use crate::{SDFExpression, SDFVolume, VolumetricFunc};
+
+    use super::Interval;
+
+    type IntervalFunc = fn(Interval, Interval) -> Interval;
+
+    #[test]
+    fn functions_bound_samples() {
+        let a = Interval::new(-1.5, 2.5);
+        let b = Interval::new(0.5, 3.0);
+        let funcs: [(IntervalFunc, fn(f64, f64) -> f64); 9] = [
+            (|a, _| a.square(), |a, _| a * a),
+            (|a, b| a * b, |a, b| a * b),
+            (|a, b| a - b, |a, b| a - b),
+            (|a, _| a.abs(), |a, _| a.abs()),
+            (|_, b| b.sqrt(), |_, b| b.sqrt()),
+            (|a, _| a.sin(), |a, _| a.sin()),
+            (|a, b| (a * b).cos(), |a, b| (a * b).cos()),
+            (|a, _| a.pow(Interval::point(2.0)), |a, _| a.powi(2)),
+            (|a, b| a.atan2(b), |a, b| a.atan2(b)),
+        ];
+
+        for (interval_func, func) in funcs {
+            let bound = interval_func(a, b);
+            for i in 0..=20 {
+                for j in 0..=20 {
+                    let x = a.lo + (a.hi - a.lo) * i as f64 / 20.0;
+                    let y = b.lo + (b.hi - b.lo) * j as f64 / 20.0;
+                    assert!(bound.contains(func(x, y)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn products_handle_zeros_and_squares() {
+        assert_eq!(
+            Interval::point(0.0) * Interval::entire(),
+            Interval::point(0.0)
+        );
+        assert_eq!(
+            Interval::new(0.0, 2.0) * Interval::new(1.0, f64::INFINITY),
+            Interval::new(0.0, f64::INFINITY)
+        );
+        assert_eq!(Interval::new(-1.0, 1.0).square(), Interval::new(0.0, 1.0));
+
+        // Multiplying a node by itself is bounded as a square.
+        let x = SDFExpression::x();
+        let volume = SDFVolume {
+            base: Vector3::repeat(-1.0),
+            size: Vector3::repeat(2.0),
+        };
+        let expected = Interval::new(0.0, 1.0);
+        assert_eq!(
+            (x.clone() * x.clone()).eval_interval(&volume),
+            Some(expected)
+        );
+        assert_eq!(
+            (x.clone() * x).compile().eval_interval(&volume),
+            Some(expected)
+        );
+    }
+}
@@ -0,0 +1,164 @@
+use nalgebra::Vector3;
+
+// A KdTree finds the points near a position.
+// The tree is implicit, the points are reordered so the median of each range is its root,
+// splitting the range along the axis stored for it.
+pub(super) struct KdTree {
+    points: Vec<Vector3<f64>>,
+    // The index of each reordered point in the original list.
+    indices: Vec<usize>,
+    axes: Vec<usize>,
+}
+
+impl KdTree {
+    pub(super) fn new(points: &[Vector3<f64>]) -> Self {
+        let mut order: Vec<usize> = (0..points.len()).collect();
+        let mut axes = vec![0; points.len()];
+        Self::build(points, &mut order, &mut axes);
+
+        Self {
+            points: order.iter().map(|i| points[*i]).collect(),
+            indices: order,
+            axes,
+        }
+    }
+
+    fn build(points: &[Vector3<f64>], order: &mut [usize], axes: &mut [usize]) {
+        if order.len() <= 1 {
+            return;
+        }
+
+        // Split along the axis where the points are most spread out.
+        let mut min = Vector3::repeat(f64::INFINITY);
+        let mut max = Vector3::repeat(f64::NEG_INFINITY);
+        for i in order.iter() {
+            min = min.inf(&points[*i]);
+            max = max.sup(&points[*i]);
+        }
+        let axis = (max - min).imax();
+
+        let mid = order.len() / 2;
+        order.select_nth_unstable_by(mid, |a, b| points[*a][axis].total_cmp(&points[*b][axis]));
+        axes[mid] = axis;
+
+        let (low, high) = order.split_at_mut(mid);
+        let (low_axes, high_axes) = axes.split_at_mut(mid);
+        Self::build(points, low, low_axes);
+        Self::build(points, &mut high[1..], &mut high_axes[1..]);
+    }
+
+    // Returns the index of the point nearest to at and its squared distance.
+    // The point with index exclude is skipped, to find the neighbours of points in the tree.
+    pub(super) fn nearest(
+        &self,
+        at: &Vector3<f64>,
+        exclude: Option<usize>,
+    ) -> Option<(usize, f64)> {
+        let mut best = None;
+        self.nearest_in(0, self.points.len(), at, exclude, &mut best);
+        best.map(|(i, dist_sq)| (self.indices[i], dist_sq))
+    }
+
+    fn nearest_in(
+        &self,
+        start: usize,
+        end: usize,
+        at: &Vector3<f64>,
+        exclude: Option<usize>,
+        best: &mut Option<(usize, f64)>,
+    ) {
+        if start >= end {
+            return;
+        }
+
+        let mid = start + (end - start) / 2;
+        let dist_sq = (self.points[mid] - at).norm_squared();
+        if exclude != Some(self.indices[mid]) && best.is_none_or(|(_, b)| dist_sq < b) {
+            *best = Some((mid, dist_sq));
+        }
+
+        let diff = at[self.axes[mid]] - self.points[mid][self.axes[mid]];
+        let (near, far) = if diff < 0.0 {
+            ((start, mid), (mid + 1, end))
+        } else {
+            ((mid + 1, end), (start, mid))
+        };
+        self.nearest_in(near.0, near.1, at, exclude, best);
+        if best.is_none_or(|(_, b)| diff * diff < b) {
+            self.nearest_in(far.0, far.1, at, exclude, best);
+        }
+    }
+
+    // Adds the indices of every point within radius of at to found.
+    pub(super) fn within(&self, at: &Vector3<f64>, radius: f64, found: &mut Vec<usize>) {
+        self.within_range(0, self.points.len(), at, radius, found);
+    }
+
+    fn within_range(
+        &self,
+        start: usize,
+        end: usize,
+        at: &Vector3<f64>,
+        radius: f64,
+        found: &mut Vec<usize>,
+    ) {
+        if start >= end {
+            return;
+        }
+
+        let mid = start + (end - start) / 2;
+        if (self.points[mid] - at).norm_squared() <= radius * radius {
+            found.push(self.indices[mid]);
+        }
+
+        let diff = at[self.axes[mid]] - self.points[mid][self.axes[mid]];
+        if diff <= radius {
+            self.within_range(start, mid, at, radius, found);
+        }
+        if diff >= -radius {
+            self.within_range(mid + 1, end, at, radius, found);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+
+    use super::KdTree;
+
+    #[test]
+    fn queries_match_brute_force() {
+        let points: Vec<_> = (0..200)
+            .map(|i| {
+                let t = i as f64;
+                Vector3::new((t * 0.37).sin(), (t * 1.91).cos(), (t * 0.13).fract())
+            })
+            .collect();
+        let tree = KdTree::new(&points);
+
+        for at in [
+            Vector3::new(0.1, -0.2, 0.5),
+            Vector3::new(2.0, 2.0, 2.0),
+            Vector3::new(-0.7, 0.7, 0.0),
+        ] {
+            let dist_sq = |i: &usize| (points[*i] - at).norm_squared();
+            let nearest = (0..points.len())
+                .min_by(|a, b| dist_sq(a).total_cmp(&dist_sq(b)))
+                .unwrap();
+            assert_eq!(tree.nearest(&at, None), Some((nearest, dist_sq(&nearest))));
+
+            let second = (0..points.len())
+                .filter(|i| *i != nearest)
+                .min_by(|a, b| dist_sq(a).total_cmp(&dist_sq(b)))
+                .unwrap();
+            assert_eq!(tree.nearest(&at, Some(nearest)).unwrap().0, second);
+
+            let mut found = Vec::new();
+            tree.within(&at, 0.5, &mut found);
+            found.sort();
+            let expected: Vec<_> = (0..points.len()).filter(|i| dist_sq(i) <= 0.25).collect();
+            assert_eq!(found, expected);
+        }
+    }
+}
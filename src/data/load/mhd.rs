@@ -0,0 +1,137 @@
+use std::path::Path;
+
+use crate::GridVolume;
+
+use super::{
+    decode_binary, parse_dims, parse_numbers, parse_vector, read_detached, Endian, HeaderReader,
+    LoadError, RawLayout, VoxelType,
+};
+
+// Parses a MetaImage header, reading the data that follows it or a data file relative to dir.
+pub(super) fn parse(bytes: &[u8], dir: Option<&Path>) -> Result<GridVolume, LoadError> {
+    let mut header = HeaderReader::new(bytes);
+    let mut layout = RawLayout::new([0; 3], VoxelType::U8);
+    let (mut voxel_type, mut dims) = (None, None);
+
+    // ElementDataFile is always the last field of the header.
+    let data_file = loop {
+        let line = header
+            .next_line()?
+            .ok_or_else(|| LoadError::Header("missing ElementDataFile".into()))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let (field, value) = line
+            .split_once('=')
+            .ok_or_else(|| LoadError::Header(format!("expected a field in \"{}\"", line)))?;
+        let (field, value) = (field.trim(), value.trim());
+        match field {
+            "ObjectType" if value != "Image" => {
+                return Err(LoadError::Unsupported(format!("{} objects", value)))
+            }
+            "NDims" if value != "3" => {
+                return Err(LoadError::Unsupported(format!(
+                    "{} dimensional volumes",
+                    value
+                )))
+            }
+            "DimSize" => dims = Some(parse_dims(field, value)?),
+            "ElementSpacing" => layout.spacing = parse_vector(field, value)?,
+            "Offset" | "Origin" | "Position" => layout.origin = parse_vector(field, value)?,
+            "ElementType" => voxel_type = Some(parse_type(value)?),
+            "BinaryDataByteOrderMSB" | "ElementByteOrderMSB" | "ByteOrderMSB" => {
+                layout.endian = match value {
+                    "True" | "true" => Endian::Big,
+                    _ => Endian::Little,
+                }
+            }
+            "HeaderSize" => {
+                layout.header_size = value
+                    .parse()
+                    .map_err(|_| LoadError::Unsupported(format!("header size of \"{}\"", value)))?
+            }
+            "TransformMatrix" | "Rotation" | "Orientation" => {
+                if parse_numbers::<f64>(field, value, 9)?
+                    != [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0]
+                {
+                    return Err(LoadError::Unsupported(
+                        "volumes that aren't axis aligned".into(),
+                    ));
+                }
+            }
+            "CompressedData" if value == "True" || value == "true" => {
+                return Err(LoadError::Unsupported("compressed data".into()))
+            }
+            "ElementNumberOfChannels" if value != "1" => {
+                return Err(LoadError::Unsupported("several channels".into()))
+            }
+            "ElementDataFile" => break value,
+            _ => {}
+        }
+    };
+
+    layout.voxel_type =
+        voxel_type.ok_or_else(|| LoadError::Header("missing ElementType".into()))?;
+    layout.dims = dims.ok_or_else(|| LoadError::Header("missing DimSize".into()))?;
+
+    let detached;
+    let data = match data_file {
+        "LOCAL" | "Local" | "local" => header.rest(),
+        "LIST" => return Err(LoadError::Unsupported("lists of data files".into())),
+        _ => {
+            detached = read_detached(dir, data_file)?;
+            &detached[..]
+        }
+    };
+    let data = layout.skip_header(data)?;
+
+    GridVolume::from_layout(decode_binary(data, &layout)?, &layout)
+}
+
+fn parse_type(value: &str) -> Result<VoxelType, LoadError> {
+    match value {
+        "MET_UCHAR" => Ok(VoxelType::U8),
+        "MET_USHORT" => Ok(VoxelType::U16),
+        "MET_SHORT" => Ok(VoxelType::I16),
+        "MET_FLOAT" => Ok(VoxelType::F32),
+        "MET_DOUBLE" => Ok(VoxelType::F64),
+        _ => Err(LoadError::Unsupported(format!("voxel type \"{}\"", value))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use nalgebra::Vector3;
+
+    use crate::GridVolume;
+
+    #[test]
+    fn metaimage_headers_load_their_data_file() {
+        let dir = std::env::temp_dir().join(format!("isosurface-mhd-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let header = "ObjectType = Image\n\
+            NDims = 3\n\
+            BinaryData = True\n\
+            BinaryDataByteOrderMSB = False\n\
+            TransformMatrix = 1 0 0 0 1 0 0 0 1\n\
+            Offset = -1 -1 0.5\n\
+            ElementSpacing = 2 2 1\n\
+            DimSize = 2 1 1\n\
+            ElementType = MET_SHORT\n\
+            ElementDataFile = scan.raw\n";
+        fs::write(dir.join("scan.mhd"), header).unwrap();
+        fs::write(dir.join("scan.raw"), [0x10, 0x00, 0xf0, 0xff]).unwrap();
+
+        let grid = GridVolume::load(dir.join("scan.mhd"));
+        fs::remove_dir_all(&dir).unwrap();
+
+        let grid = grid.unwrap();
+        assert_eq!(grid.values(), [16.0, -16.0]);
+        assert_eq!(grid.origin(), Vector3::new(-1.0, -1.0, 0.5));
+        assert_eq!(grid.spacing(), Vector3::new(2.0, 2.0, 1.0));
+    }
+}
@@ -0,0 +1,362 @@
+mod mhd;
+mod nrrd;
+mod vtk;
+
+use std::{error::Error, fmt::Display, fs, io, path::Path};
+
+use nalgebra::Vector3;
+
+use super::GridVolume;
+
+// The type of each voxel in a binary volume.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum VoxelType {
+    U8,
+    U16,
+    I16,
+    F32,
+    F64,
+}
+
+impl VoxelType {
+    // The size of a voxel in bytes.
+    pub fn size(&self) -> usize {
+        match self {
+            VoxelType::U8 => 1,
+            VoxelType::U16 | VoxelType::I16 => 2,
+            VoxelType::F32 => 4,
+            VoxelType::F64 => 8,
+        }
+    }
+}
+
+// The byte order of multi-byte voxels.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Endian {
+    Little,
+    Big,
+}
+
+// Describes how a raw binary volume is stored.
+// Voxels are stored with x varying fastest, then y, then z.
+#[derive(Clone, Debug)]
+pub struct RawLayout {
+    pub dims: [usize; 3],
+    pub voxel_type: VoxelType,
+    pub endian: Endian,
+    pub origin: Vector3<f64>,
+    pub spacing: Vector3<f64>,
+    // The number of bytes to skip before the first voxel.
+    pub header_size: usize,
+}
+
+impl RawLayout {
+    pub fn new(dims: [usize; 3], voxel_type: VoxelType) -> Self {
+        Self {
+            dims,
+            voxel_type,
+            endian: Endian::Little,
+            origin: Vector3::zeros(),
+            spacing: Vector3::new(1.0, 1.0, 1.0),
+            header_size: 0,
+        }
+    }
+
+    // The number of voxels, checked so hostile headers can't overflow it.
+    fn voxel_count(&self) -> Result<usize, LoadError> {
+        self.dims
+            .iter()
+            .try_fold(1usize, |count, n| count.checked_mul(*n))
+            .ok_or_else(|| LoadError::Header(format!("dimensions {:?} are too large", self.dims)))
+    }
+
+    // The data after the header_size bytes at the start of bytes.
+    fn skip_header<'a>(&self, bytes: &'a [u8]) -> Result<&'a [u8], LoadError> {
+        bytes.get(self.header_size..).ok_or_else(|| {
+            LoadError::Header(format!(
+                "header size of {} is larger than the {} bytes of data",
+                self.header_size,
+                bytes.len()
+            ))
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    // The header is malformed or missing a required field.
+    Header(String),
+    // The file uses a feature of its format that isn't supported, such as compression.
+    Unsupported(String),
+    // The file holds fewer bytes of voxel data than its header describes.
+    TooShort { expected: usize, actual: usize },
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::Io(err) => write!(f, "{}", err),
+            LoadError::Header(message) => write!(f, "invalid header: {}", message),
+            LoadError::Unsupported(message) => write!(f, "unsupported: {}", message),
+            LoadError::TooShort { expected, actual } => write!(
+                f,
+                "expected {} bytes of voxel data but found {}",
+                expected, actual
+            ),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from(err: io::Error) -> Self {
+        LoadError::Io(err)
+    }
+}
+
+impl GridVolume {
+    // Loads a volume, choosing the format from the file's extension.
+    // NRRD (.nrrd, .nhdr), legacy VTK structured points (.vtk) and MetaImage (.mhd, .mha)
+    // files are supported. Raw files have no header, use load_raw for them.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, LoadError> {
+        let path = path.as_ref();
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        match extension.to_ascii_lowercase().as_str() {
+            "nrrd" | "nhdr" => Self::load_nrrd(path),
+            "vtk" => Self::load_vtk(path),
+            "mhd" | "mha" => Self::load_mhd(path),
+            _ => Err(LoadError::Unsupported(format!(
+                "unknown volume file extension \"{}\"",
+                extension
+            ))),
+        }
+    }
+
+    // Loads a headerless binary volume stored as described by layout.
+    pub fn load_raw(path: impl AsRef<Path>, layout: &RawLayout) -> Result<Self, LoadError> {
+        Self::from_raw_bytes(&fs::read(path)?, layout)
+    }
+
+    pub fn from_raw_bytes(bytes: &[u8], layout: &RawLayout) -> Result<Self, LoadError> {
+        let values = decode_binary(layout.skip_header(bytes)?, layout)?;
+        Self::from_layout(values, layout)
+    }
+
+    pub fn load_nrrd(path: impl AsRef<Path>) -> Result<Self, LoadError> {
+        let path = path.as_ref();
+        nrrd::parse(&fs::read(path)?, path.parent())
+    }
+
+    pub fn load_vtk(path: impl AsRef<Path>) -> Result<Self, LoadError> {
+        vtk::parse(&fs::read(path)?)
+    }
+
+    pub fn load_mhd(path: impl AsRef<Path>) -> Result<Self, LoadError> {
+        let path = path.as_ref();
+        mhd::parse(&fs::read(path)?, path.parent())
+    }
+
+    fn from_layout(values: Vec<f64>, layout: &RawLayout) -> Result<Self, LoadError> {
+        if layout.dims.contains(&0) {
+            return Err(LoadError::Header("volumes need at least one voxel".into()));
+        }
+
+        Ok(Self::new(
+            values,
+            layout.dims,
+            layout.origin,
+            layout.spacing,
+        ))
+    }
+}
+
+// Reads the voxels described by layout from the start of data.
+// Any bytes after the last voxel are ignored.
+fn decode_binary(data: &[u8], layout: &RawLayout) -> Result<Vec<f64>, LoadError> {
+    let size = layout.voxel_type.size();
+    let expected = layout
+        .voxel_count()?
+        .checked_mul(size)
+        .ok_or_else(|| LoadError::Header(format!("dimensions {:?} are too large", layout.dims)))?;
+    if data.len() < expected {
+        return Err(LoadError::TooShort {
+            expected,
+            actual: data.len(),
+        });
+    }
+
+    let values = data[..expected].chunks_exact(size).map(|bytes| {
+        // Voxels are read as big endian, so little endian voxels are reversed first.
+        let mut buf = [0; 8];
+        buf[..size].copy_from_slice(bytes);
+        if layout.endian == Endian::Little {
+            buf[..size].reverse();
+        }
+
+        match layout.voxel_type {
+            VoxelType::U8 => buf[0] as f64,
+            VoxelType::U16 => u16::from_be_bytes([buf[0], buf[1]]) as f64,
+            VoxelType::I16 => i16::from_be_bytes([buf[0], buf[1]]) as f64,
+            VoxelType::F32 => f32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+            VoxelType::F64 => f64::from_be_bytes(buf),
+        }
+    });
+
+    Ok(values.collect())
+}
+
+// Reads whitespace separated voxels from the start of data.
+fn decode_ascii(data: &[u8], layout: &RawLayout) -> Result<Vec<f64>, LoadError> {
+    let count = layout.voxel_count()?;
+    let text = String::from_utf8_lossy(data);
+    let values = text
+        .split_whitespace()
+        .take(count)
+        .map(|v| {
+            v.parse::<f64>()
+                .map_err(|_| LoadError::Header(format!("invalid voxel value \"{}\"", v)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if values.len() < count {
+        return Err(LoadError::Header(format!(
+            "expected {} voxel values but found {}",
+            count,
+            values.len()
+        )));
+    }
+
+    Ok(values)
+}
+
+// Reads a data file named in a header, relative to the header's directory.
+fn read_detached(dir: Option<&Path>, file: &str) -> Result<Vec<u8>, LoadError> {
+    let dir = dir.ok_or_else(|| {
+        LoadError::Unsupported("detached data in a header that wasn't loaded from a file".into())
+    })?;
+    Ok(fs::read(dir.join(file))?)
+}
+
+// Splits the text lines of a header off the binary data following it.
+#[derive(Clone)]
+struct HeaderReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> HeaderReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    // Returns the next line without its line ending, or None at the end of the file.
+    fn next_line(&mut self) -> Result<Option<&'a str>, LoadError> {
+        if self.pos >= self.bytes.len() {
+            return Ok(None);
+        }
+
+        let rest = &self.bytes[self.pos..];
+        let len = rest.iter().position(|b| *b == b'\n').unwrap_or(rest.len());
+        self.pos += (len + 1).min(rest.len());
+
+        let line = std::str::from_utf8(&rest[..len])
+            .map_err(|_| LoadError::Header("header isn't valid text".into()))?;
+        Ok(Some(line.strip_suffix('\r').unwrap_or(line)))
+    }
+
+    // The bytes after the last line read.
+    fn rest(&self) -> &'a [u8] {
+        &self.bytes[self.pos..]
+    }
+}
+
+// Parses exactly n whitespace separated numbers.
+fn parse_numbers<T: std::str::FromStr>(
+    field: &str,
+    value: &str,
+    n: usize,
+) -> Result<Vec<T>, LoadError> {
+    let numbers = value
+        .split_whitespace()
+        .map(|v| v.parse::<T>())
+        .collect::<Result<Vec<_>, _>>()
+        .ok()
+        .filter(|v| v.len() == n);
+
+    numbers.ok_or_else(|| LoadError::Header(format!("{} should be {} numbers", field, n)))
+}
+
+fn parse_vector(field: &str, value: &str) -> Result<Vector3<f64>, LoadError> {
+    Ok(Vector3::from_vec(parse_numbers(field, value, 3)?))
+}
+
+fn parse_dims(field: &str, value: &str) -> Result<[usize; 3], LoadError> {
+    let dims = parse_numbers::<usize>(field, value, 3)?;
+    Ok([dims[0], dims[1], dims[2]])
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+
+    use crate::{Endian, GridVolume, LoadError, RawLayout, VoxelType};
+
+    #[test]
+    fn raw_voxels_are_decoded() {
+        let mut layout = RawLayout::new([2, 1, 2], VoxelType::I16);
+        layout.endian = Endian::Big;
+        layout.header_size = 3;
+        layout.spacing = Vector3::new(0.5, 1.0, 2.0);
+
+        let bytes = [9, 9, 9, 0, 1, 0xff, 0xfe, 0x01, 0x00, 0x80, 0x00];
+        let grid = GridVolume::from_raw_bytes(&bytes, &layout).unwrap();
+        assert_eq!(grid.values(), [1.0, -2.0, 256.0, -32768.0]);
+        assert_eq!(grid.bounds().size, Vector3::new(0.5, 0.0, 2.0));
+
+        let layout = RawLayout::new([2, 1, 1], VoxelType::F32);
+        let bytes = [1.5f32.to_le_bytes(), (-0.25f32).to_le_bytes()].concat();
+        let grid = GridVolume::from_raw_bytes(&bytes, &layout).unwrap();
+        assert_eq!(grid.values(), [1.5, -0.25]);
+
+        let short = GridVolume::from_raw_bytes(&bytes[..6], &layout);
+        assert!(matches!(
+            short,
+            Err(LoadError::TooShort {
+                expected: 8,
+                actual: 6
+            })
+        ));
+    }
+
+    #[test]
+    fn hostile_headers_are_rejected() {
+        let header = "NRRD0004\ntype: uchar\ndimension: 3\nsizes: 4294967296 4294967296 1\n\n";
+        let huge = [header.as_bytes(), &[0; 16]].concat();
+        assert!(matches!(
+            super::nrrd::parse(&huge, None),
+            Err(LoadError::Header(_))
+        ));
+
+        let layout = RawLayout::new([usize::MAX / 2, 1, 1], VoxelType::F64);
+        assert!(matches!(
+            GridVolume::from_raw_bytes(&[0; 16], &layout),
+            Err(LoadError::Header(_))
+        ));
+
+        let mut layout = RawLayout::new([1, 1, 1], VoxelType::U8);
+        layout.header_size = 100;
+        assert!(matches!(
+            GridVolume::from_raw_bytes(&[0; 16], &layout),
+            Err(LoadError::Header(_))
+        ));
+    }
+}
@@ -0,0 +1,182 @@
+use std::path::Path;
+
+use nalgebra::Vector3;
+
+use crate::GridVolume;
+
+use super::{
+    decode_ascii, decode_binary, parse_dims, parse_vector, read_detached, Endian, HeaderReader,
+    LoadError, RawLayout, VoxelType,
+};
+
+// Parses a NRRD file, reading detached data files relative to dir.
+// Only raw and ascii encodings of 3D volumes are supported.
+pub(super) fn parse(bytes: &[u8], dir: Option<&Path>) -> Result<GridVolume, LoadError> {
+    let mut header = HeaderReader::new(bytes);
+    if !header
+        .next_line()?
+        .is_some_and(|magic| magic.starts_with("NRRD"))
+    {
+        return Err(LoadError::Header("missing NRRD magic".into()));
+    }
+
+    let mut layout = RawLayout::new([0; 3], VoxelType::U8);
+    let (mut voxel_type, mut dims, mut encoding, mut data_file) = (None, None, "raw", None);
+
+    // The header ends at the first empty line.
+    while let Some(line) = header.next_line()? {
+        if line.is_empty() {
+            break;
+        }
+        // Comments and key/value pairs don't affect the volume.
+        if line.starts_with('#') || line.contains(":=") {
+            continue;
+        }
+
+        let (field, value) = line
+            .split_once(':')
+            .ok_or_else(|| LoadError::Header(format!("expected a field in \"{}\"", line)))?;
+        let value = value.trim();
+        match field {
+            "type" => voxel_type = Some(parse_type(value)?),
+            "dimension" if value != "3" => {
+                return Err(LoadError::Unsupported(format!(
+                    "{} dimensional volumes",
+                    value
+                )))
+            }
+            "sizes" => dims = Some(parse_dims(field, value)?),
+            "encoding" => encoding = value,
+            "endian" => {
+                layout.endian = match value {
+                    "little" => Endian::Little,
+                    "big" => Endian::Big,
+                    _ => return Err(LoadError::Header(format!("unknown endian \"{}\"", value))),
+                }
+            }
+            "spacings" => layout.spacing = parse_vector(field, value)?,
+            "space directions" => layout.spacing = parse_directions(value)?,
+            "space origin" => {
+                layout.origin = parse_vector(field, &value.replace(['(', ')', ','], " "))?
+            }
+            "byte skip" => {
+                layout.header_size = value
+                    .parse()
+                    .map_err(|_| LoadError::Unsupported(format!("byte skip of \"{}\"", value)))?
+            }
+            "data file" | "datafile" => data_file = Some(value),
+            _ => {}
+        }
+    }
+
+    layout.voxel_type = voxel_type.ok_or_else(|| LoadError::Header("missing type".into()))?;
+    layout.dims = dims.ok_or_else(|| LoadError::Header("missing sizes".into()))?;
+
+    let detached;
+    let data = match data_file {
+        Some(file) => {
+            detached = read_detached(dir, file)?;
+            &detached[..]
+        }
+        None => header.rest(),
+    };
+    let data = layout.skip_header(data)?;
+
+    let values = match encoding {
+        "raw" => decode_binary(data, &layout)?,
+        "ascii" | "text" | "txt" => decode_ascii(data, &layout)?,
+        _ => {
+            return Err(LoadError::Unsupported(format!(
+                "{} encoded NRRD data",
+                encoding
+            )))
+        }
+    };
+
+    GridVolume::from_layout(values, &layout)
+}
+
+fn parse_type(value: &str) -> Result<VoxelType, LoadError> {
+    match value {
+        "uchar" | "unsigned char" | "uint8" | "uint8_t" => Ok(VoxelType::U8),
+        "ushort" | "unsigned short" | "unsigned short int" | "uint16" | "uint16_t" => {
+            Ok(VoxelType::U16)
+        }
+        "short" | "short int" | "signed short" | "signed short int" | "int16" | "int16_t" => {
+            Ok(VoxelType::I16)
+        }
+        "float" => Ok(VoxelType::F32),
+        "double" => Ok(VoxelType::F64),
+        _ => Err(LoadError::Unsupported(format!("voxel type \"{}\"", value))),
+    }
+}
+
+// Space directions are a vector per axis, like (0.5,0,0) (0,0.5,0) (0,0,2).
+// Only axis aligned volumes are supported, so each vector gives the spacing along its axis.
+fn parse_directions(value: &str) -> Result<Vector3<f64>, LoadError> {
+    let directions = value
+        .split(')')
+        .map(|d| d.trim().trim_start_matches('('))
+        .filter(|d| !d.is_empty())
+        .map(|d| parse_vector("space directions", &d.replace(',', " ")))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if directions.len() != 3 {
+        return Err(LoadError::Header(
+            "space directions should be 3 vectors".into(),
+        ));
+    }
+
+    let mut spacing = Vector3::zeros();
+    for (axis, direction) in directions.iter().enumerate() {
+        if (0..3).any(|i| i != axis && direction[i] != 0.0) {
+            return Err(LoadError::Unsupported(
+                "volumes that aren't axis aligned".into(),
+            ));
+        }
+        spacing[axis] = direction[axis];
+    }
+
+    Ok(spacing)
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+
+    use crate::{GridVolume, LoadError};
+
+    use super::parse;
+
+    #[test]
+    fn nrrd_headers_describe_voxels() {
+        let header = "NRRD0004\n\
+            # A comment\n\
+            type: unsigned short\n\
+            dimension: 3\n\
+            space: left-posterior-superior\n\
+            sizes: 2 2 1\n\
+            space directions: (0.5,0,0) (0,0.5,0) (0,0,2)\n\
+            space origin: (1,-2,3)\n\
+            endian: big\n\
+            encoding: raw\n\
+            modality:=CT\n\
+            \n";
+        let bytes = [header.as_bytes(), &[0, 1, 0, 2, 1, 0, 0xff, 0xff]].concat();
+        let grid = parse(&bytes, None).unwrap();
+        assert_eq!(grid.values(), [1.0, 2.0, 256.0, 65535.0]);
+        assert_eq!(grid.origin(), Vector3::new(1.0, -2.0, 3.0));
+        assert_eq!(grid.spacing(), Vector3::new(0.5, 0.5, 2.0));
+
+        let ascii =
+            "NRRD0001\ntype: double\ndimension: 3\nsizes: 1 1 3\nencoding: ascii\n\n1.5 -2\n4\n";
+        let grid: GridVolume = parse(ascii.as_bytes(), None).unwrap();
+        assert_eq!(grid.values(), [1.5, -2.0, 4.0]);
+
+        let gzip = ascii.replace("ascii", "gzip");
+        assert!(matches!(
+            parse(gzip.as_bytes(), None),
+            Err(LoadError::Unsupported(_))
+        ));
+    }
+}
@@ -0,0 +1,119 @@
+use crate::GridVolume;
+
+use super::{
+    decode_ascii, decode_binary, parse_dims, parse_vector, Endian, HeaderReader, LoadError,
+    RawLayout, VoxelType,
+};
+
+// Parses a legacy VTK file holding STRUCTURED_POINTS with a single scalar per point.
+pub(super) fn parse(bytes: &[u8]) -> Result<GridVolume, LoadError> {
+    let mut header = HeaderReader::new(bytes);
+    if !header
+        .next_line()?
+        .is_some_and(|magic| magic.starts_with("# vtk DataFile"))
+    {
+        return Err(LoadError::Header("missing VTK version line".into()));
+    }
+
+    // The second line is a title.
+    header.next_line()?;
+    let binary = match header.next_line()?.map(str::trim) {
+        Some("BINARY") => true,
+        Some("ASCII") => false,
+        _ => return Err(LoadError::Header("expected ASCII or BINARY".into())),
+    };
+
+    // Binary VTK data is always big endian.
+    let mut layout = RawLayout::new([0; 3], VoxelType::U8);
+    layout.endian = Endian::Big;
+    let mut dims = None;
+
+    loop {
+        let line = header
+            .next_line()?
+            .ok_or_else(|| LoadError::Header("missing SCALARS".into()))?;
+        let (keyword, value) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+        match keyword {
+            "" => {}
+            "DATASET" if value.trim() != "STRUCTURED_POINTS" => {
+                return Err(LoadError::Unsupported(format!("{} datasets", value.trim())))
+            }
+            "DIMENSIONS" => dims = Some(parse_dims(keyword, value)?),
+            "SPACING" | "ASPECT_RATIO" => layout.spacing = parse_vector(keyword, value)?,
+            "ORIGIN" => layout.origin = parse_vector(keyword, value)?,
+            "CELL_DATA" => return Err(LoadError::Unsupported("cell data".into())),
+            "SCALARS" => {
+                let mut fields = value.split_whitespace().skip(1);
+                layout.voxel_type = parse_type(fields.next().unwrap_or(""))?;
+                if fields.next().is_some_and(|components| components != "1") {
+                    return Err(LoadError::Unsupported(
+                        "scalars with several components".into(),
+                    ));
+                }
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    // The lookup table line is optional, so check the raw bytes as binary data may follow.
+    if header.rest().starts_with(b"LOOKUP_TABLE") {
+        header.next_line()?;
+    }
+
+    layout.dims = dims.ok_or_else(|| LoadError::Header("missing DIMENSIONS".into()))?;
+    let values = if binary {
+        decode_binary(header.rest(), &layout)?
+    } else {
+        decode_ascii(header.rest(), &layout)?
+    };
+
+    GridVolume::from_layout(values, &layout)
+}
+
+fn parse_type(value: &str) -> Result<VoxelType, LoadError> {
+    match value {
+        "unsigned_char" => Ok(VoxelType::U8),
+        "unsigned_short" => Ok(VoxelType::U16),
+        "short" => Ok(VoxelType::I16),
+        "float" => Ok(VoxelType::F32),
+        "double" => Ok(VoxelType::F64),
+        _ => Err(LoadError::Unsupported(format!("voxel type \"{}\"", value))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+
+    use super::parse;
+
+    #[test]
+    fn vtk_structured_points_are_read() {
+        let header = "# vtk DataFile Version 3.0\n\
+            A scan\n\
+            BINARY\n\
+            DATASET STRUCTURED_POINTS\n\
+            DIMENSIONS 3 1 1\n\
+            ORIGIN 0 1 2\n\
+            SPACING 0.5 0.5 0.5\n\
+            POINT_DATA 3\n\
+            SCALARS density float 1\n\
+            LOOKUP_TABLE default\n";
+        let data = [1.0f32, -0.5, 8.0].map(f32::to_be_bytes).concat();
+        let grid = parse(&[header.as_bytes(), &data].concat()).unwrap();
+        assert_eq!(grid.values(), [1.0, -0.5, 8.0]);
+        assert_eq!(grid.origin(), Vector3::new(0.0, 1.0, 2.0));
+        assert_eq!(grid.spacing(), Vector3::new(0.5, 0.5, 0.5));
+
+        // Binary data can follow the scalars line directly.
+        let header = header.replace("LOOKUP_TABLE default\n", "");
+        let grid = parse(&[header.as_bytes(), &data].concat()).unwrap();
+        assert_eq!(grid.values(), [1.0, -0.5, 8.0]);
+
+        let ascii = "# vtk DataFile Version 2.0\ntitle\nASCII\n\nDATASET STRUCTURED_POINTS\n\
+            DIMENSIONS 1 2 1\nPOINT_DATA 2\nSCALARS v unsigned_char\n7 300\n";
+        let grid = parse(ascii.as_bytes()).unwrap();
+        assert_eq!(grid.values(), [7.0, 300.0]);
+    }
+}
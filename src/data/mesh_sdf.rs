@@ -0,0 +1,408 @@
+use std::collections::HashMap;
+
+use nalgebra::Vector3;
+
+use crate::MeshBuffers;
+
+use super::{Interval, SDFVolume, VolumetricFunc};
+
+// The number of triangles stored in each leaf of the BVH.
+const LEAF_SIZE: usize = 4;
+
+// A MeshSDF is the exact signed distance to a closed triangle mesh, positive outside.
+// Triangles must be wound counterclockwise seen from outside, like the faces of a cube whose
+// normals (b - a) x (c - a) point outwards. The sign comes from angle weighted pseudo-normals,
+// which is exact for closed, consistently wound meshes without self intersections.
+// Meshes can be combined with other functions through CSG, for example a union of a MeshSDF
+// and an SDFExpression.
+pub struct MeshSDF {
+    vertices: Vec<Vector3<f64>>,
+    triangles: Vec<[usize; 3]>,
+    face_normals: Vec<Vector3<f64>>,
+    vertex_normals: Vec<Vector3<f64>>,
+    // The pseudo-normal of the edge from vertex i to vertex i + 1 of each triangle.
+    edge_normals: Vec<[Vector3<f64>; 3]>,
+    bvh: Vec<BVHNode>,
+    // Triangle indices ordered so each leaf of the BVH holds a contiguous range.
+    order: Vec<usize>,
+}
+
+#[derive(Clone, Copy)]
+struct Bounds {
+    min: Vector3<f64>,
+    max: Vector3<f64>,
+}
+
+impl Bounds {
+    fn empty() -> Self {
+        Self {
+            min: Vector3::repeat(f64::INFINITY),
+            max: Vector3::repeat(f64::NEG_INFINITY),
+        }
+    }
+
+    fn grow(&mut self, p: &Vector3<f64>) {
+        self.min = self.min.inf(p);
+        self.max = self.max.sup(p);
+    }
+
+    // The squared distance from p to the nearest point in these bounds.
+    fn distance_squared(&self, p: &Vector3<f64>) -> f64 {
+        let below = self.min - p;
+        let above = p - self.max;
+        below.sup(&above).sup(&Vector3::zeros()).norm_squared()
+    }
+}
+
+enum BVHNode {
+    Leaf {
+        bounds: Bounds,
+        start: usize,
+        end: usize,
+    },
+    Inner {
+        bounds: Bounds,
+        children: [usize; 2],
+    },
+}
+
+impl BVHNode {
+    fn bounds(&self) -> &Bounds {
+        match self {
+            BVHNode::Leaf { bounds, .. } | BVHNode::Inner { bounds, .. } => bounds,
+        }
+    }
+}
+
+// The part of a triangle closest to a point, which decides the pseudo-normal used for its sign.
+#[derive(Clone, Copy)]
+enum Feature {
+    Face,
+    Vertex(usize),
+    // The edge from vertex i to vertex i + 1.
+    Edge(usize),
+}
+
+struct Closest {
+    dist_sq: f64,
+    point: Vector3<f64>,
+    triangle: usize,
+    feature: Feature,
+}
+
+impl MeshSDF {
+    // Creates the distance function of a mesh given its vertices and a list of triangle indices.
+    // Panics if the indices aren't whole triangles or refer to missing vertices.
+    pub fn new(vertices: Vec<Vector3<f64>>, indices: &[usize]) -> Self {
+        assert!(
+            indices.len().is_multiple_of(3),
+            "Mesh indices should be a list of triangles."
+        );
+        assert!(!indices.is_empty(), "Meshes need at least one triangle.");
+        assert!(
+            indices.iter().all(|i| *i < vertices.len()),
+            "Mesh indices must refer to vertices."
+        );
+
+        let triangles: Vec<[usize; 3]> = indices
+            .chunks_exact(3)
+            .map(|t| [t[0], t[1], t[2]])
+            .collect();
+        let corners = |t: &[usize; 3]| t.map(|i| vertices[i]);
+
+        let face_normals: Vec<_> = triangles
+            .iter()
+            .map(|t| {
+                let [a, b, c] = corners(t);
+                (b - a)
+                    .cross(&(c - a))
+                    .try_normalize(0.0)
+                    .unwrap_or_default()
+            })
+            .collect();
+
+        // Vertex normals weight each face by its angle at the vertex.
+        let mut vertex_normals = vec![Vector3::zeros(); vertices.len()];
+        for (t, normal) in triangles.iter().zip(&face_normals) {
+            let p = corners(t);
+            for i in 0..3 {
+                let (u, v) = (p[(i + 1) % 3] - p[i], p[(i + 2) % 3] - p[i]);
+                vertex_normals[t[i]] += u.angle(&v) * normal;
+            }
+        }
+
+        // Edge normals are the sum of the normals of the faces sharing the edge.
+        let edge_key = |t: &[usize; 3], i: usize| {
+            let (a, b) = (t[i], t[(i + 1) % 3]);
+            (a.min(b), a.max(b))
+        };
+        let mut edge_sums = HashMap::new();
+        for (t, normal) in triangles.iter().zip(&face_normals) {
+            for i in 0..3 {
+                *edge_sums.entry(edge_key(t, i)).or_insert(Vector3::zeros()) += normal;
+            }
+        }
+        let edge_normals = triangles
+            .iter()
+            .map(|t| [0, 1, 2].map(|i| edge_sums[&edge_key(t, i)]))
+            .collect();
+
+        let mut mesh = Self {
+            vertices,
+            triangles,
+            face_normals,
+            vertex_normals,
+            edge_normals,
+            bvh: Vec::new(),
+            order: Vec::new(),
+        };
+        mesh.build_bvh();
+        mesh
+    }
+
+    pub fn from_mesh(mesh: &MeshBuffers) -> Self {
+        Self::new(mesh.0.clone(), &mesh.1)
+    }
+
+    fn build_bvh(&mut self) {
+        let centroids: Vec<_> = self
+            .triangles
+            .iter()
+            .map(|t| t.map(|i| self.vertices[i]).iter().sum::<Vector3<f64>>() / 3.0)
+            .collect();
+
+        let mut order: Vec<_> = (0..self.triangles.len()).collect();
+        self.build_node(&mut order, 0, &centroids);
+        self.order = order;
+    }
+
+    // Adds a node holding the triangles in tris, which start at offset in the final order.
+    fn build_node(
+        &mut self,
+        tris: &mut [usize],
+        offset: usize,
+        centroids: &[Vector3<f64>],
+    ) -> usize {
+        let mut bounds = Bounds::empty();
+        let mut centroid_bounds = Bounds::empty();
+        for t in tris.iter() {
+            for v in self.triangles[*t] {
+                bounds.grow(&self.vertices[v]);
+            }
+            centroid_bounds.grow(&centroids[*t]);
+        }
+
+        let id = self.bvh.len();
+        if tris.len() <= LEAF_SIZE {
+            self.bvh.push(BVHNode::Leaf {
+                bounds,
+                start: offset,
+                end: offset + tris.len(),
+            });
+            return id;
+        }
+
+        // Split at the median centroid along the axis where the centroids are most spread out.
+        let axis = (centroid_bounds.max - centroid_bounds.min).imax();
+        let mid = tris.len() / 2;
+        tris.select_nth_unstable_by(mid, |a, b| {
+            centroids[*a][axis].total_cmp(&centroids[*b][axis])
+        });
+
+        self.bvh.push(BVHNode::Inner {
+            bounds,
+            children: [0, 0],
+        });
+        let (low, high) = tris.split_at_mut(mid);
+        let children = [
+            self.build_node(low, offset, centroids),
+            self.build_node(high, offset + mid, centroids),
+        ];
+        if let BVHNode::Inner { children: c, .. } = &mut self.bvh[id] {
+            *c = children;
+        }
+        id
+    }
+
+    fn closest(&self, at: &Vector3<f64>) -> Closest {
+        let mut best = Closest {
+            dist_sq: f64::INFINITY,
+            point: *at,
+            triangle: 0,
+            feature: Feature::Face,
+        };
+
+        let mut stack = vec![(0.0, 0)];
+        while let Some((dist_sq, node)) = stack.pop() {
+            if dist_sq >= best.dist_sq {
+                continue;
+            }
+
+            match &self.bvh[node] {
+                BVHNode::Leaf { start, end, .. } => {
+                    for t in &self.order[*start..*end] {
+                        let (point, feature) = self.closest_on_triangle(*t, at);
+                        let dist_sq = (at - point).norm_squared();
+                        if dist_sq < best.dist_sq {
+                            best = Closest {
+                                dist_sq,
+                                point,
+                                triangle: *t,
+                                feature,
+                            };
+                        }
+                    }
+                }
+                BVHNode::Inner { children, .. } => {
+                    // The nearer child is pushed last so it's searched first.
+                    let mut children =
+                        children.map(|c| (self.bvh[c].bounds().distance_squared(at), c));
+                    if children[0].0 < children[1].0 {
+                        children.swap(0, 1);
+                    }
+                    stack.extend(children);
+                }
+            }
+        }
+
+        best
+    }
+
+    // The closest point to p on a triangle, from Ericson's Real-Time Collision Detection.
+    fn closest_on_triangle(&self, t: usize, p: &Vector3<f64>) -> (Vector3<f64>, Feature) {
+        let [a, b, c] = self.triangles[t].map(|i| self.vertices[i]);
+        let (ab, ac, ap) = (b - a, c - a, p - a);
+
+        let (d1, d2) = (ab.dot(&ap), ac.dot(&ap));
+        if d1 <= 0.0 && d2 <= 0.0 {
+            return (a, Feature::Vertex(0));
+        }
+
+        let bp = p - b;
+        let (d3, d4) = (ab.dot(&bp), ac.dot(&bp));
+        if d3 >= 0.0 && d4 <= d3 {
+            return (b, Feature::Vertex(1));
+        }
+
+        let vc = d1 * d4 - d3 * d2;
+        if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+            return (a + ab * (d1 / (d1 - d3)), Feature::Edge(0));
+        }
+
+        let cp = p - c;
+        let (d5, d6) = (ab.dot(&cp), ac.dot(&cp));
+        if d6 >= 0.0 && d5 <= d6 {
+            return (c, Feature::Vertex(2));
+        }
+
+        let vb = d5 * d2 - d1 * d6;
+        if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+            return (a + ac * (d2 / (d2 - d6)), Feature::Edge(2));
+        }
+
+        let va = d3 * d6 - d5 * d4;
+        if va <= 0.0 && d4 - d3 >= 0.0 && d5 - d6 >= 0.0 {
+            let w = (d4 - d3) / ((d4 - d3) + (d5 - d6));
+            return (b + (c - b) * w, Feature::Edge(1));
+        }
+
+        let denom = 1.0 / (va + vb + vc);
+        (a + ab * (vb * denom) + ac * (vc * denom), Feature::Face)
+    }
+
+    fn pseudo_normal(&self, closest: &Closest) -> Vector3<f64> {
+        let t = closest.triangle;
+        match closest.feature {
+            Feature::Face => self.face_normals[t],
+            Feature::Vertex(i) => self.vertex_normals[self.triangles[t][i]],
+            Feature::Edge(i) => self.edge_normals[t][i],
+        }
+    }
+}
+
+impl VolumetricFunc for MeshSDF {
+    fn eval(&self, at: &Vector3<f64>) -> f64 {
+        self.eval_with_grad(at).0
+    }
+
+    fn grad(&self, at: &Vector3<f64>) -> Vector3<f64> {
+        self.eval_with_grad(at).1
+    }
+
+    fn eval_with_grad(&self, at: &Vector3<f64>) -> (f64, Vector3<f64>) {
+        let closest = self.closest(at);
+        let offset = at - closest.point;
+        let normal = self.pseudo_normal(&closest);
+        let sign = if offset.dot(&normal) < 0.0 { -1.0 } else { 1.0 };
+
+        let dist = closest.dist_sq.sqrt();
+        let grad = match offset.try_normalize(0.0) {
+            Some(direction) => direction * sign,
+            None => normal.try_normalize(0.0).unwrap_or_default(),
+        };
+        (dist * sign, grad)
+    }
+
+    // Distances change by at most the distance moved, so the value at the center of the volume
+    // bounds the values in it.
+    fn eval_interval(&self, volume: &SDFVolume) -> Option<Interval> {
+        let center = volume.base + volume.size * 0.5;
+        let radius = volume.size.norm() * 0.5;
+        let dist = self.eval(&center);
+        Some(Interval::new(dist - radius, dist + radius))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+
+    use crate::{MeshSDF, SDFExpression, VolumetricFunc, CSG};
+
+    #[test]
+    fn mesh_distances_match_primitives() {
+        // A cube from (0, 0, 0) to (2, 1, 1), wound counterclockwise seen from outside.
+        let vertices = (0..8)
+            .map(|i| Vector3::new((i & 1) as f64 * 2.0, (i >> 1 & 1) as f64, (i >> 2) as f64))
+            .collect();
+        let indices = [
+            0, 2, 1, 1, 2, 3, // z = 0
+            4, 5, 6, 5, 7, 6, // z = 1
+            0, 1, 4, 1, 5, 4, // y = 0
+            2, 6, 3, 3, 6, 7, // y = 1
+            0, 4, 2, 2, 4, 6, // x = 0
+            1, 3, 5, 3, 7, 5, // x = 2
+        ];
+        let mesh = MeshSDF::new(vertices, &indices);
+        let cuboid =
+            SDFExpression::cuboid(Vector3::new(1.0, 0.5, 0.5), Vector3::new(1.0, 0.5, 0.5));
+
+        for at in [
+            Vector3::new(0.5, 0.4, 0.3),
+            Vector3::new(1.2, 0.5, 0.5),
+            Vector3::new(-0.5, 0.5, 0.5),
+            Vector3::new(2.5, 1.5, 0.5),
+            Vector3::new(-1.0, -1.0, 2.0),
+            Vector3::new(1.0, 0.1, -0.3),
+            Vector3::new(2.0, 0.0, 0.0),
+        ] {
+            assert!((mesh.eval(&at) - cuboid.eval(&at)).abs() < 1e-12, "{}", at);
+        }
+
+        let at = Vector3::new(2.5, 1.5, 0.5);
+        assert!((mesh.grad(&at) - cuboid.grad(&at)).norm() < 1e-12);
+
+        // Meshes are combined with expressions through CSG.
+        let sphere = SDFExpression::sphere(Vector3::new(3.0, 0.5, 0.5), 0.75);
+        let union = CSG::union(mesh, sphere);
+        let at = Vector3::new(2.5, 0.5, 0.5);
+        assert!((union.eval(&at) + 0.25).abs() < 1e-12);
+        assert!((union.grad(&Vector3::new(3.5, 0.5, 0.5)) - Vector3::x()).norm() < 1e-12);
+    }
+
+    #[test]
+    #[should_panic]
+    fn missing_vertices_panic() {
+        MeshSDF::new(vec![Vector3::zeros(); 3], &[0, 1, 3]);
+    }
+}
@@ -0,0 +1,243 @@
+use nalgebra::Vector3;
+
+use super::{kdtree::KdTree, Interval, SDFVolume, VolumetricFunc};
+
+// The falloff of each ball's contribution with distance, reaching 0 at its radius.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Kernel {
+    // The soft objects polynomial of Wyvill, McPheeters and Wyvill, which is 0.5 at half the radius.
+    #[default]
+    Wyvill,
+    // A Gaussian times the window (1 - q)^2, so it and its slope fall smoothly to 0 at the radius.
+    Gaussian,
+}
+
+// Sharpness of the Gaussian kernel, the Gaussian is exp(-GAUSSIAN_SHARPNESS) at the radius.
+const GAUSSIAN_SHARPNESS: f64 = 4.0;
+
+impl Kernel {
+    // The kernel and its derivative for q, the squared distance divided by the squared radius.
+    // Both reach 0 at q = 1 without jumping and the kernel decreases until then.
+    fn eval(&self, q: f64) -> (f64, f64) {
+        if q >= 1.0 {
+            return (0.0, 0.0);
+        }
+
+        match self {
+            Kernel::Wyvill => (
+                1.0 + q * (-22.0 / 9.0 + q * (17.0 / 9.0 - q * 4.0 / 9.0)),
+                -22.0 / 9.0 + q * (34.0 / 9.0 - q * 4.0 / 3.0),
+            ),
+            Kernel::Gaussian => {
+                let e = (-GAUSSIAN_SHARPNESS * q).exp();
+                let w = 1.0 - q;
+                (e * w * w, -e * w * (GAUSSIAN_SHARPNESS * w + 2.0))
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Metaball {
+    pub center: Vector3<f64>,
+    pub radius: f64,
+    // Negative weights carve into the other balls.
+    pub weight: f64,
+}
+
+// Metaballs are blobs around weighted centers that merge smoothly where they are close.
+// The field is the sum of each ball's kernel times its weight, and the surface is where it
+// reaches the threshold. The value is the threshold minus the field, so it's negative inside,
+// but unlike a distance it isn't scaled to the distance to the surface.
+pub struct Metaballs {
+    balls: Vec<Metaball>,
+    tree: KdTree,
+    max_radius: f64,
+    kernel: Kernel,
+    threshold: f64,
+}
+
+impl Metaballs {
+    // Creates metaballs with the Wyvill kernel and a threshold of 0.5.
+    pub fn new(balls: Vec<Metaball>) -> Self {
+        assert!(
+            balls.iter().all(|ball| ball.radius > 0.0),
+            "Metaballs need positive radii."
+        );
+
+        let centers: Vec<_> = balls.iter().map(|ball| ball.center).collect();
+        let max_radius = balls.iter().map(|ball| ball.radius).fold(0.0, f64::max);
+        Self {
+            tree: KdTree::new(&centers),
+            balls,
+            max_radius,
+            kernel: Kernel::default(),
+            threshold: 0.5,
+        }
+    }
+
+    pub fn with_kernel(mut self, kernel: Kernel) -> Self {
+        self.kernel = kernel;
+        self
+    }
+
+    // Sets the field value of the surface, lower thresholds give bigger blobs.
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        assert!(threshold > 0.0, "Thresholds must be positive.");
+        self.threshold = threshold;
+        self
+    }
+
+    pub fn balls(&self) -> &[Metaball] {
+        &self.balls
+    }
+
+    pub fn kernel(&self) -> Kernel {
+        self.kernel
+    }
+
+    pub fn threshold(&self) -> f64 {
+        self.threshold
+    }
+
+    // A volume containing every ball, with a margin so the surface doesn't touch its sides.
+    pub fn bounds(&self) -> SDFVolume {
+        let mut min = Vector3::repeat(f64::INFINITY);
+        let mut max = Vector3::repeat(f64::NEG_INFINITY);
+        for ball in self.balls.iter() {
+            min = min.inf(&ball.center.add_scalar(-ball.radius));
+            max = max.sup(&ball.center.add_scalar(ball.radius));
+        }
+        if self.balls.is_empty() {
+            (min, max) = (Vector3::repeat(-1.0), Vector3::repeat(1.0));
+        }
+
+        let margin = (max - min) * 0.05;
+        SDFVolume {
+            base: min - margin,
+            size: max - min + margin * 2.0,
+        }
+    }
+
+    // The indices of the balls that may reach within distance of at.
+    fn nearby(&self, at: &Vector3<f64>, distance: f64) -> Vec<usize> {
+        let mut found = Vec::new();
+        self.tree.within(at, self.max_radius + distance, &mut found);
+        found
+    }
+}
+
+impl VolumetricFunc for Metaballs {
+    fn eval(&self, at: &Vector3<f64>) -> f64 {
+        self.eval_with_grad(at).0
+    }
+
+    fn grad(&self, at: &Vector3<f64>) -> Vector3<f64> {
+        self.eval_with_grad(at).1
+    }
+
+    fn eval_with_grad(&self, at: &Vector3<f64>) -> (f64, Vector3<f64>) {
+        let (mut field, mut field_grad) = (0.0, Vector3::zeros());
+        for i in self.nearby(at, 0.0) {
+            let ball = &self.balls[i];
+            let offset = at - ball.center;
+            let inv_r2 = 1.0 / (ball.radius * ball.radius);
+            let (k, dk) = self.kernel.eval(offset.norm_squared() * inv_r2);
+
+            field += ball.weight * k;
+            field_grad += offset * (ball.weight * dk * 2.0 * inv_r2);
+        }
+
+        (self.threshold - field, -field_grad)
+    }
+
+    fn eval_interval(&self, volume: &SDFVolume) -> Option<Interval> {
+        let center = volume.base + volume.size * 0.5;
+        let half = volume.size * 0.5;
+
+        // The kernels decrease with distance, so each ball's contribution is bounded by its
+        // values at the nearest and furthest points of the volume.
+        let mut field = Interval::point(0.0);
+        for i in self.nearby(&center, half.norm()) {
+            let ball = &self.balls[i];
+            let offset = (ball.center - center).abs();
+            let near = (offset - half).sup(&Vector3::zeros()).norm_squared();
+            let far = (offset + half).norm_squared();
+
+            let inv_r2 = 1.0 / (ball.radius * ball.radius);
+            let high = self.kernel.eval(near * inv_r2).0 * ball.weight;
+            let low = self.kernel.eval(far * inv_r2).0 * ball.weight;
+            field = field + Interval::new(low.min(high), low.max(high));
+        }
+
+        Some(Interval::point(self.threshold) - field)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+
+    use crate::{find_isosurface, Kernel, Metaball, Metaballs, SolverSettings, VolumetricFunc};
+
+    #[test]
+    fn metaballs_merge_and_mesh() {
+        let ball = |x: f64, weight: f64| Metaball {
+            center: Vector3::new(x, 0.0, 0.0),
+            radius: 2.0,
+            weight,
+        };
+
+        // A lone Wyvill ball reaches the default threshold at half its radius.
+        let single = Metaballs::new(vec![ball(0.0, 1.0)]);
+        assert!(single.eval(&Vector3::new(0.0, 1.0, 0.0)).abs() < 1e-12);
+        assert_eq!(single.eval(&Vector3::new(0.0, 0.0, 2.5)), 0.5);
+
+        // Two balls whose lone surfaces wouldn't touch join in the middle, while a negative one
+        // carves the field around it.
+        let balls = Metaballs::new(vec![ball(-1.1, 1.0), ball(1.1, 1.0), ball(5.0, -1.0)]);
+        assert!(balls.eval(&Vector3::zeros()) < 0.0);
+        assert!(balls.eval(&Vector3::new(4.0, 0.0, 0.0)) > 0.0);
+
+        for kernel in [Kernel::Wyvill, Kernel::Gaussian] {
+            let balls = Metaballs::new(balls.balls().to_vec()).with_kernel(kernel);
+            let at = Vector3::new(0.3, 0.4, -0.2);
+            let step = 1e-6;
+            let numeric = Vector3::from_fn(|i, _| {
+                let offset = Vector3::ith(i, step);
+                (balls.eval(&(at + offset)) - balls.eval(&(at - offset))) / (2.0 * step)
+            });
+            assert!((balls.grad(&at) - numeric).norm() < 1e-6);
+
+            // The gradient fades out at the edge of a ball instead of jumping to 0.
+            let single = Metaballs::new(vec![ball(0.0, 1.0)]).with_kernel(kernel);
+            let at = Vector3::new(0.0, 1.998, 0.0);
+            let numeric = Vector3::from_fn(|i, _| {
+                let offset = Vector3::ith(i, step);
+                (single.eval(&(at + offset)) - single.eval(&(at - offset))) / (2.0 * step)
+            });
+            assert!((single.grad(&at) - numeric).norm() < 1e-6);
+            assert!(single.grad(&at).norm() < 1e-2);
+
+            let volume = balls.bounds();
+            let interval = balls.eval_interval(&volume).unwrap();
+            for i in 0..64 {
+                let t = Vector3::new(i % 4, i / 4 % 4, i / 16).cast::<f64>() / 3.0;
+                assert!(
+                    interval.contains(balls.eval(&(volume.base + volume.size.component_mul(&t))))
+                );
+            }
+
+            let settings = SolverSettings {
+                min_octree_depth: 2,
+                max_octree_depth: 3,
+                ..Default::default()
+            };
+            let mesh = find_isosurface(&balls, &volume, &settings);
+            assert!(!mesh.0.is_empty());
+            for vert in mesh.0 {
+                assert!(balls.eval(&vert).abs() < 1e-3, "{}", vert);
+            }
+        }
+    }
+}
@@ -1,6 +1,35 @@
+mod closure;
+pub use closure::ClosureFunc;
+
+mod csg;
+pub use csg::{CSGOp, CSG};
+
+mod dual;
+pub use dual::{AutoDiff, Dual, Scalar, ScalarFunc};
+
+mod grid;
+pub use grid::{GridVolume, Interpolation};
+
+mod interval;
+pub use interval::Interval;
+
+mod kdtree;
+
+mod load;
+pub use load::{Endian, LoadError, RawLayout, VoxelType};
+
+mod metaballs;
+pub use metaballs::{Kernel, Metaball, Metaballs};
+
+mod mesh_sdf;
+pub use mesh_sdf::MeshSDF;
+
+mod point_cloud;
+pub use point_cloud::PointCloudSDF;
+
 pub(crate) mod sdf;
 
-use nalgebra::{SVector, Vector3};
+use nalgebra::{Matrix3, SVector, Vector3};
 use std::{fmt::Display, marker::ConstParamTy};
 
 use crate::subspace::Subspace;
@@ -8,9 +37,48 @@ use crate::subspace::Subspace;
 pub trait VolumetricFunc: Send + Sync {
     fn eval(&self, at: &Vector3<f64>) -> f64;
     fn grad(&self, at: &Vector3<f64>) -> Vector3<f64>;
+
+    // Returns the value and gradient at a position.
+    // Functions able to compute both in one pass should override this.
+    fn eval_with_grad(&self, at: &Vector3<f64>) -> (f64, Vector3<f64>) {
+        (self.eval(at), self.grad(at))
+    }
+
+    // Evaluates this function at each point.
+    // Functions able to evaluate many points faster than one at a time should override this
+    // and grad_many, the solver evaluates points in batches wherever it can.
+    fn eval_many(&self, points: &[Vector3<f64>]) -> Vec<f64> {
+        points.iter().map(|at| self.eval(at)).collect()
+    }
+
+    // Returns the gradient at each point.
+    fn grad_many(&self, points: &[Vector3<f64>]) -> Vec<Vector3<f64>> {
+        points.iter().map(|at| self.grad(at)).collect()
+    }
+
+    // Returns the matrix of second derivatives at a position.
+    // By default it's approximated with central differences of the gradient.
+    fn hessian(&self, at: &Vector3<f64>) -> Matrix3<f64> {
+        let step = 1e-5;
+        let columns = [0, 1, 2].map(|i| {
+            let offset = Vector3::ith(i, step);
+            (self.grad(&(at + offset)) - self.grad(&(at - offset))) / (2.0 * step)
+        });
+
+        // The exact hessian is symmetric, averaging both halves reduces the error.
+        let hessian = Matrix3::from_columns(&columns);
+        (hessian + hessian.transpose()) * 0.5
+    }
+
+    // Returns bounds on the value of this function over a volume, if they can be computed.
+    // The bounds must contain every value in the volume but don't need to be tight.
+    fn eval_interval(&self, _volume: &SDFVolume) -> Option<Interval> {
+        None
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ConstParamTy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Dimension {
     X,
     Y,
@@ -35,6 +103,11 @@ pub struct SDFVolume {
 }
 
 impl SDFVolume {
+    // The range of each dimension in this volume.
+    pub fn intervals(&self) -> [Interval; 3] {
+        [0, 1, 2].map(|i| Interval::new(self.base[i], self.base[i] + self.size[i]))
+    }
+
     pub(crate) fn real_pos<const N: usize, S>(
         &self,
         norm_pos: &SVector<f64, N>,
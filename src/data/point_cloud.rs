@@ -0,0 +1,205 @@
+use nalgebra::Vector3;
+
+use super::{kdtree::KdTree, VolumetricFunc};
+
+// Points further than this many radii away don't affect the function.
+const CUTOFF: f64 = 3.0;
+
+// Between this many radii from the nearest point and CUTOFF, the value blends into the distance
+// to the nearest point.
+const BLEND_START: f64 = 2.0;
+
+// A PointCloudSDF reconstructs a surface from points with outward normals using implicit moving
+// least squares. The value at a position is a Gaussian weighted average of the distances to the
+// tangent planes of the nearby points, which approximates the signed distance near the surface.
+// Far from every point it blends into the distance to the nearest point, signed by its plane.
+// Like any fit of planes, curved surfaces are moved slightly away from their center of curvature,
+// by about curvature * radius^2 / 2.
+pub struct PointCloudSDF {
+    points: Vec<Vector3<f64>>,
+    normals: Vec<Vector3<f64>>,
+    tree: KdTree,
+    radius: f64,
+}
+
+impl PointCloudSDF {
+    // Creates a function from points and their normals, which don't need to be normalized.
+    // The radius of the weights is estimated from the spacing of the points.
+    pub fn new(points: Vec<Vector3<f64>>, normals: Vec<Vector3<f64>>) -> Self {
+        assert_eq!(points.len(), normals.len(), "Every point needs a normal.");
+        assert!(!points.is_empty(), "Point clouds need at least one point.");
+
+        let normals = normals
+            .iter()
+            .map(|n| n.try_normalize(0.0).unwrap_or_default())
+            .collect();
+        let tree = KdTree::new(&points);
+        let mut cloud = Self {
+            points,
+            normals,
+            tree,
+            radius: 1.0,
+        };
+
+        // Twice the average distance from each point to its nearest neighbour.
+        let spacing = (0..cloud.points.len())
+            .filter_map(|i| cloud.tree.nearest(&cloud.points[i], Some(i)))
+            .map(|(_, dist_sq)| dist_sq.sqrt())
+            .sum::<f64>()
+            / cloud.points.len() as f64;
+        if spacing > 0.0 {
+            cloud.radius = 2.0 * spacing;
+        }
+        cloud
+    }
+
+    // Sets the radius of the weights, larger radii give smoother surfaces that fill bigger holes.
+    pub fn with_radius(mut self, radius: f64) -> Self {
+        self.radius = radius;
+        self
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    // The weighted average of the distances to the planes of the points within the cutoff.
+    fn weighted_distance(&self, at: &Vector3<f64>) -> (f64, Vector3<f64>) {
+        let mut found = Vec::new();
+        self.tree.within(at, CUTOFF * self.radius, &mut found);
+
+        // The value is num / den, whose gradients are summed alongside them.
+        let inv_r2 = 1.0 / (self.radius * self.radius);
+        let (mut num, mut den) = (0.0, 0.0);
+        let (mut num_grad, mut den_grad) = (Vector3::zeros(), Vector3::zeros());
+        for i in found {
+            let offset = at - self.points[i];
+            let w = (-offset.norm_squared() * inv_r2).exp();
+            let w_grad = offset * (-2.0 * inv_r2 * w);
+            let plane = offset.dot(&self.normals[i]);
+
+            num += w * plane;
+            den += w;
+            num_grad += w_grad * plane + self.normals[i] * w;
+            den_grad += w_grad;
+        }
+
+        let val = num / den;
+        (val, (num_grad - den_grad * val) / den)
+    }
+}
+
+impl VolumetricFunc for PointCloudSDF {
+    fn eval(&self, at: &Vector3<f64>) -> f64 {
+        self.eval_with_grad(at).0
+    }
+
+    fn grad(&self, at: &Vector3<f64>) -> Vector3<f64> {
+        self.eval_with_grad(at).1
+    }
+
+    fn eval_with_grad(&self, at: &Vector3<f64>) -> (f64, Vector3<f64>) {
+        let (nearest, dist_sq) = self.tree.nearest(at, None).unwrap();
+        let s = dist_sq.sqrt() / self.radius;
+        if s < BLEND_START {
+            return self.weighted_distance(at);
+        }
+
+        let offset = at - self.points[nearest];
+        let sign = if offset.dot(&self.normals[nearest]) < 0.0 {
+            -1.0
+        } else {
+            1.0
+        };
+        let (far, far_grad) = (offset.norm() * sign, offset.normalize() * sign);
+        if s >= CUTOFF {
+            return (far, far_grad);
+        }
+
+        // Smoothstep from the weighted distance to the nearest distance across the band.
+        let (near, near_grad) = self.weighted_distance(at);
+        let u = (s - BLEND_START) / (CUTOFF - BLEND_START);
+        let t = u * u * (3.0 - 2.0 * u);
+        let t_grad =
+            offset.normalize() * (6.0 * u * (1.0 - u) / ((CUTOFF - BLEND_START) * self.radius));
+        (
+            near + (far - near) * t,
+            near_grad + (far_grad - near_grad) * t + t_grad * (far - near),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use nalgebra::Vector3;
+
+    use crate::{find_isosurface, PointCloudSDF, SDFVolume, SolverSettings, VolumetricFunc};
+
+    #[test]
+    fn sphere_is_reconstructed_from_points() {
+        // Points spread evenly over a sphere of radius 1.
+        let n = 400;
+        let points: Vec<_> = (0..n)
+            .map(|i| {
+                let z = 1.0 - (2.0 * i as f64 + 1.0) / n as f64;
+                let theta = PI * (3.0 - 5.0f64.sqrt()) * i as f64;
+                let r = (1.0 - z * z).sqrt();
+                Vector3::new(r * theta.cos(), r * theta.sin(), z)
+            })
+            .collect();
+        let cloud = PointCloudSDF::new(points.clone(), points);
+
+        // The sphere has a curvature of 1, so near it the surface is moved outwards by radius^2 / 2.
+        // Far from the points the distance to the nearest one is used.
+        let shrink = 0.5 * cloud.radius().powi(2);
+        for (at, dist) in [
+            (Vector3::new(0.0, 0.0, 1.1), 0.1 - shrink),
+            (Vector3::new(0.6, -0.48, 0.0), -0.232 - shrink),
+            (Vector3::new(3.0, 4.0, 0.0), 4.0),
+        ] {
+            assert!((cloud.eval(&at) - dist).abs() < 0.01, "{}", at);
+        }
+
+        let at = Vector3::new(0.5, 0.5, 0.6);
+        assert!((cloud.grad(&at) - at.normalize()).norm() < 0.1);
+
+        // Moving out through the band where the value blends into the distance to the nearest
+        // point, nothing jumps at the cutoff and the gradient stays exact.
+        let step = 1e-3;
+        for i in 0..(3.0 * cloud.radius() / step) as usize {
+            let at = Vector3::new(0.0, 0.0, 1.0 + 1.5 * cloud.radius() + i as f64 * step);
+            let next = at + Vector3::new(0.0, 0.0, step);
+            assert!(
+                (cloud.eval(&next) - cloud.eval(&at)).abs() < 2.0 * step,
+                "{}",
+                at
+            );
+
+            let h = 1e-6;
+            let numeric = Vector3::from_fn(|i, _| {
+                let offset = Vector3::ith(i, h);
+                (cloud.eval(&(at + offset)) - cloud.eval(&(at - offset))) / (2.0 * h)
+            });
+            assert!((cloud.grad(&at) - numeric).norm() < 1e-4, "{}", at);
+        }
+
+        let volume = SDFVolume {
+            base: Vector3::new(-1.5, -1.5, -1.5),
+            size: Vector3::new(3.0, 3.0, 3.0),
+        };
+        let settings = SolverSettings {
+            min_octree_depth: 2,
+            max_octree_depth: 2,
+            dual_sample_subdivisions: 1,
+            vert_fitting_error: 1e-6,
+            ..Default::default()
+        };
+        let mesh = find_isosurface(&cloud, &volume, &settings);
+        assert!(!mesh.0.is_empty());
+        for vert in mesh.0 {
+            assert!((vert.norm() - shrink - 1.0).abs() < 0.02, "{}", vert.norm());
+        }
+    }
+}
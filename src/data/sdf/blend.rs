@@ -0,0 +1,127 @@
+use super::SDFExpression;
+
+// Blended versions of min and max.
+// Each blend only differs from the hard operation where the two expressions are within radius
+// of each other. They panic unless radius is greater than 0.
+impl SDFExpression {
+    // A polynomial smooth minimum, its gradient is continuous across the blend.
+    pub fn smooth_min(a: Self, b: Self, radius: f64) -> Self {
+        assert!(radius > 0.0, "Blend radii must be positive.");
+        let h = Self::max(
+            Self::from(radius) - (a.clone() - b.clone()).abs(),
+            0.0.into(),
+        );
+
+        Self::min(a, b) - h.clone() * h * (0.25 / radius).into()
+    }
+
+    // A polynomial smooth maximum, its gradient is continuous across the blend.
+    pub fn smooth_max(a: Self, b: Self, radius: f64) -> Self {
+        -Self::smooth_min(-a, -b, radius)
+    }
+
+    // An exponential smooth minimum, it is smooth everywhere but never exactly matches min.
+    pub fn smooth_min_exp(a: Self, b: Self, radius: f64) -> Self {
+        assert!(radius > 0.0, "Blend radii must be positive.");
+        // Equivalent to -radius * ln(e^(-a / radius) + e^(-b / radius)) without overflowing.
+        let diff = (a.clone() - b.clone()).abs() * (-1.0 / radius).into();
+        let soft = (diff.exp() + 1.0.into()).ln();
+
+        Self::min(a, b) - soft * radius.into()
+    }
+
+    // An exponential smooth maximum, it is smooth everywhere but never exactly matches max.
+    pub fn smooth_max_exp(a: Self, b: Self, radius: f64) -> Self {
+        -Self::smooth_min_exp(-a, -b, radius)
+    }
+
+    // Removes b from a, smoothly blending where they meet.
+    pub fn smooth_subtract(a: Self, b: Self, radius: f64) -> Self {
+        Self::smooth_max(a, -b, radius)
+    }
+
+    // A minimum that replaces the crease where a and b meet with a 45 degree chamfer.
+    pub fn chamfer_min(a: Self, b: Self, radius: f64) -> Self {
+        assert!(radius > 0.0, "Blend radii must be positive.");
+        let chamfer =
+            (a.clone() + b.clone() - radius.into()) * std::f64::consts::FRAC_1_SQRT_2.into();
+
+        Self::min(Self::min(a, b), chamfer)
+    }
+
+    // A maximum that replaces the crease where a and b meet with a 45 degree chamfer.
+    pub fn chamfer_max(a: Self, b: Self, radius: f64) -> Self {
+        -Self::chamfer_min(-a, -b, radius)
+    }
+
+    // A minimum that replaces the crease where a and b meet with a quarter circle of radius.
+    pub fn round_min(a: Self, b: Self, radius: f64) -> Self {
+        assert!(radius > 0.0, "Blend radii must be positive.");
+        let ua = Self::max(Self::from(radius) - a.clone(), 0.0.into());
+        let ub = Self::max(Self::from(radius) - b.clone(), 0.0.into());
+        let len_sq = ua.clone() * ua + ub.clone() * ub;
+        let min = Self::min(a, b);
+
+        // Outside the blend the offset is 0, select min directly to avoid the gradient of sqrt(0).
+        let blend = Self::max(radius.into(), min.clone()) - len_sq.clone().sqrt();
+        Self::select(len_sq, 0.0.into(), blend, min)
+    }
+
+    // A maximum that replaces the crease where a and b meet with a quarter circle of radius.
+    pub fn round_max(a: Self, b: Self, radius: f64) -> Self {
+        -Self::round_min(-a, -b, radius)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+
+    use crate::{SDFExpression, VolumetricFunc};
+
+    #[test]
+    fn blends_match_hard_operations_away_from_the_blend() {
+        let a = SDFExpression::sphere(Vector3::new(-1.0, 0.0, 0.0), 1.5);
+        let b = SDFExpression::sphere(Vector3::new(1.0, 0.0, 0.0), 1.5);
+        let blends = [
+            SDFExpression::smooth_min(a.clone(), b.clone(), 0.5),
+            SDFExpression::smooth_min_exp(a.clone(), b.clone(), 0.01),
+            SDFExpression::chamfer_min(a.clone(), b.clone(), 0.5),
+            SDFExpression::round_min(a.clone(), b.clone(), 0.5),
+        ];
+        let hard = SDFExpression::min(a, b);
+
+        let at = Vector3::new(-3.0, 0.5, 0.0);
+        for blend in blends {
+            assert!((blend.eval(&at) - hard.eval(&at)).abs() < 1e-9);
+            assert!((blend.grad(&at) - hard.grad(&at)).norm() < 1e-9);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_radius_panics() {
+        SDFExpression::smooth_min(SDFExpression::x(), SDFExpression::y(), 0.0);
+    }
+
+    #[test]
+    fn smooth_blend_gradients_are_continuous() {
+        let a = SDFExpression::x();
+        let b = SDFExpression::y();
+        let blends = [
+            SDFExpression::smooth_min(a.clone(), b.clone(), 0.5),
+            SDFExpression::smooth_max(a.clone(), b.clone(), 0.5),
+            SDFExpression::smooth_min_exp(a.clone(), b.clone(), 0.5),
+            SDFExpression::smooth_max_exp(a.clone(), b.clone(), 0.5),
+        ];
+
+        // Step across the edges of the blend region and the line where a == b.
+        for blend in blends {
+            for crossing in [-0.5, 0.0, 0.5] {
+                let below = blend.grad(&Vector3::new(crossing - 1e-9, 0.0, 0.0));
+                let above = blend.grad(&Vector3::new(crossing + 1e-9, 0.0, 0.0));
+                assert!((below - above).norm() < 1e-6);
+            }
+        }
+    }
+}
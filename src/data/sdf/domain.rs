@@ -0,0 +1,154 @@
+use nalgebra::Vector3;
+
+use crate::Dimension;
+
+use super::SDFExpression;
+
+const AXES: [Dimension; 3] = [Dimension::X, Dimension::Y, Dimension::Z];
+
+// Domain operators remap the position before this expression is evaluated, so their cost
+// doesn't depend on how many copies of the surface they produce.
+// Repetition is only exact while each copy of the surface fits inside its own cell.
+impl SDFExpression {
+    // Repeats the surface infinitely with the given period along each axis.
+    // Axes with a period of 0 aren't repeated.
+    pub fn repeat(self, period: Vector3<f64>) -> Self {
+        let dims = AXES.map(|d| {
+            let (i, p) = (d as usize, Self::from(d));
+            if period[i] == 0.0 {
+                return p;
+            }
+
+            let cell = (p.clone() * (1.0 / period[i]).into() + 0.5.into()).floor();
+            p - cell * period[i].into()
+        });
+
+        self.substitute(dims)
+    }
+
+    // Repeats the surface count times along each axis, with the copies centered on the origin.
+    pub fn repeat_bounded(self, period: Vector3<f64>, count: [usize; 3]) -> Self {
+        let dims = AXES.map(|d| {
+            let (i, p) = (d as usize, Self::from(d));
+            if period[i] == 0.0 || count[i] <= 1 {
+                return p;
+            }
+
+            // The index of the nearest copy, counting from 0 at the lowest copy.
+            let first = -(count[i] as f64 - 1.0) / 2.0;
+            let cell = (p.clone() * (1.0 / period[i]).into() + (0.5 - first).into()).floor();
+            let cell = Self::clamp(cell, 0.0, count[i] as f64 - 1.0);
+            p - (cell + first.into()) * period[i].into()
+        });
+
+        self.substitute(dims)
+    }
+
+    // Mirrors the part of the surface in front of a plane to the back of it.
+    // The surface behind the plane is discarded.
+    pub fn mirror(self, normal: Vector3<f64>, point: Vector3<f64>) -> Self {
+        let normal = normal.normalize();
+        let dist = Self::dot(
+            Self::centered_axes(&point),
+            [normal.x, normal.y, normal.z].map(Self::from),
+        );
+
+        // Points behind the plane are moved by twice their distance to it.
+        let shift = dist.clone().abs() - dist;
+        let dims = AXES.map(|d| Self::from(d) + shift.clone() * normal[d as usize].into());
+        self.substitute(dims)
+    }
+
+    // Twists the surface about an axis through the origin by rate radians per unit along it.
+    // The result overestimates distances, scaling it down by sqrt(1 + (rate * r)^2) for
+    // surfaces within r of the axis makes it a bound again.
+    pub fn twist(self, axis: Dimension, rate: f64) -> Self {
+        let (u, v) = Self::plane_axes(axis);
+        self.rotate_plane(u, v, Self::from(axis) * (-rate).into())
+    }
+
+    // Bends the along axis towards the towards axis, rate being the curvature of the bent axis.
+    // Like twist, the result isn't an exact distance.
+    pub fn bend(self, along: Dimension, towards: Dimension, rate: f64) -> Self {
+        self.rotate_plane(along, towards, Self::from(along) * (-rate).into())
+    }
+
+    // Rotates the position in the plane of u and v by angle, which may vary with position.
+    fn rotate_plane(self, u: Dimension, v: Dimension, angle: Self) -> Self {
+        let (cos, sin) = (angle.clone().cos(), angle.sin());
+        let (pu, pv) = (Self::from(u), Self::from(v));
+
+        let mut dims = AXES.map(Self::from);
+        dims[u as usize] = cos.clone() * pu.clone() - sin.clone() * pv.clone();
+        dims[v as usize] = sin * pu + cos * pv;
+        self.substitute(dims)
+    }
+
+    // The two axes perpendicular to axis.
+    fn plane_axes(axis: Dimension) -> (Dimension, Dimension) {
+        match axis {
+            Dimension::X => (Dimension::Y, Dimension::Z),
+            Dimension::Y => (Dimension::Z, Dimension::X),
+            Dimension::Z => (Dimension::X, Dimension::Y),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{UnitQuaternion, Vector3};
+
+    use crate::{Dimension, SDFExpression, VolumetricFunc};
+
+    #[test]
+    fn domain_operators_remap_position() {
+        let shape = SDFExpression::cuboid(Vector3::new(0.2, 0.0, 0.0), Vector3::new(0.3, 0.2, 0.1));
+        let at = Vector3::new(0.1, 0.15, -0.05);
+        let period = Vector3::new(2.0, 3.0, 0.0);
+
+        let repeated = shape.clone().repeat(period);
+        let bounded = shape.clone().repeat_bounded(period, [3, 2, 1]);
+        for (i, j) in [(-5, 7), (1, -1), (0, 0)] {
+            let offset = Vector3::new(i as f64 * 2.0, j as f64 * 3.0, 0.0);
+            assert!((repeated.eval(&(at + offset)) - shape.eval(&at)).abs() < 1e-12);
+        }
+        for (i, j) in [(-1.0, -0.5), (1.0, 0.5), (0.0, -0.5)] {
+            let offset = Vector3::new(i * 2.0, j * 3.0, 0.0);
+            assert!((bounded.eval(&(at + offset)) - shape.eval(&at)).abs() < 1e-12);
+        }
+        // Beyond the last copy the nearest copy is used.
+        let far = Vector3::new(20.0, 0.0, 0.0);
+        let last = Vector3::new(2.0, 1.5, 0.0);
+        assert_eq!(bounded.eval(&far), shape.eval(&(far - last)));
+
+        let mirrored = shape.clone().mirror(Vector3::x(), Vector3::zeros());
+        assert_eq!(
+            mirrored.eval(&at),
+            mirrored.eval(&Vector3::new(-at.x, at.y, at.z))
+        );
+
+        // A twist rotates each slice perpendicular to the axis by a different angle.
+        let twisted = shape.clone().twist(Dimension::Z, 0.7);
+        let slice = Vector3::new(0.3, -0.4, 2.0);
+        let rotation = UnitQuaternion::from_axis_angle(&Vector3::z_axis(), -0.7 * slice.z);
+        assert!((twisted.eval(&slice) - shape.eval(&(rotation * slice))).abs() < 1e-12);
+
+        // A bend rotates the position in the XY plane by an angle proportional to x.
+        let rate = 0.8;
+        let bent = shape.clone().bend(Dimension::X, Dimension::Y, rate);
+        let p = Vector3::new(0.6, 0.3, -0.05);
+        let (s, c) = (-rate * p.x).sin_cos();
+        let q = Vector3::new(c * p.x - s * p.y, s * p.x + c * p.y, p.z);
+        assert!((bent.eval(&p) - shape.eval(&q)).abs() < 1e-12);
+
+        // The gradient follows from the chain rule, the angle changing with x by -rate.
+        let g = shape.grad(&q);
+        let dq_dx = Vector3::new(
+            c + (s * p.x + c * p.y) * rate,
+            s - (c * p.x - s * p.y) * rate,
+            0.0,
+        );
+        let expected = Vector3::new(g.dot(&dq_dx), c * g.y - s * g.x, g.z);
+        assert!((bent.grad(&p) - expected).norm() < 1e-12);
+    }
+}
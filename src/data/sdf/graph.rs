@@ -0,0 +1,335 @@
+use std::collections::HashMap;
+
+use crate::data::{Dimension, Interval, Scalar};
+
+use super::node::{BinaryOp, NodeId, SDFNode, UnaryOp};
+
+// The number of points eval_many evaluates together.
+const BATCH: usize = 64;
+
+// An SDFGraph is a directed acyclic graph of SDFNodes stored in topological order.
+// Nodes are hash-consed, pushing a node identical to an existing one returns the existing node,
+// so common subexpressions are only stored and evaluated once.
+#[derive(Clone, Default)]
+pub(super) struct SDFGraph {
+    nodes: Vec<SDFNode>,
+    lookup: HashMap<SDFNode, NodeId>,
+}
+
+impl SDFGraph {
+    pub(super) fn push(&mut self, node: SDFNode) -> NodeId {
+        if let Some(id) = self.lookup.get(&node) {
+            return *id;
+        }
+
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(node.clone());
+        self.lookup.insert(node, id);
+        id
+    }
+
+    pub(super) fn node(&self, id: NodeId) -> &SDFNode {
+        &self.nodes[id.0]
+    }
+
+    #[cfg(feature = "serde")]
+    pub(super) fn nodes(&self) -> &[SDFNode] {
+        &self.nodes
+    }
+
+    pub(super) fn constant(&mut self, val: f64) -> NodeId {
+        self.push(SDFNode::Const(val))
+    }
+
+    pub(super) fn unary(&mut self, op: UnaryOp, a: NodeId) -> NodeId {
+        self.push(SDFNode::Unary(op, a))
+    }
+
+    pub(super) fn binary(&mut self, op: BinaryOp, a: NodeId, b: NodeId) -> NodeId {
+        self.push(SDFNode::Binary(op, a, b))
+    }
+
+    pub(super) fn add(&mut self, a: NodeId, b: NodeId) -> NodeId {
+        self.binary(BinaryOp::Add, a, b)
+    }
+
+    pub(super) fn mul(&mut self, a: NodeId, b: NodeId) -> NodeId {
+        self.binary(BinaryOp::Mul, a, b)
+    }
+
+    // Returns a mask of the nodes root depends on, including root.
+    pub(super) fn reachable(&self, root: NodeId) -> Vec<bool> {
+        let mut mask = vec![false; root.0 + 1];
+        mask[root.0] = true;
+
+        // Operands always come before the nodes using them, so one backward pass is enough.
+        for i in (0..=root.0).rev() {
+            if mask[i] {
+                for operand in self.nodes[i].operands() {
+                    mask[operand.0] = true;
+                }
+            }
+        }
+
+        mask
+    }
+
+    // Returns a mask of the nodes any of roots depend on.
+    pub(super) fn reachable_from<const N: usize>(&self, roots: [NodeId; N]) -> Vec<bool> {
+        let last = roots.iter().map(|r| r.0).max().unwrap_or_default();
+        let mut mask = vec![false; last + 1];
+        for root in roots {
+            for (i, reachable) in self.reachable(root).into_iter().enumerate() {
+                mask[i] |= reachable;
+            }
+        }
+
+        mask
+    }
+
+    // Copies the nodes root depends on from other into this graph and returns the copied root.
+    pub(super) fn import(&mut self, other: &SDFGraph, root: NodeId) -> NodeId {
+        self.import_with(other, root, None)
+    }
+
+    // Like import, but every Dim node in other is replaced by the corresponding node in dims.
+    pub(super) fn substitute(
+        &mut self,
+        other: &SDFGraph,
+        root: NodeId,
+        dims: [NodeId; 3],
+    ) -> NodeId {
+        self.import_with(other, root, Some(dims))
+    }
+
+    fn import_with(&mut self, other: &SDFGraph, root: NodeId, dims: Option<[NodeId; 3]>) -> NodeId {
+        let mask = other.reachable(root);
+        let mut map = vec![NodeId(0); root.0 + 1];
+
+        for (i, node) in other.nodes[..=root.0].iter().enumerate() {
+            if !mask[i] {
+                continue;
+            }
+
+            map[i] = match (node, dims) {
+                (SDFNode::Dim(d), Some(dims)) => dims[*d as usize],
+                _ => self.push(node.map_operands(|id| map[id.0])),
+            };
+        }
+
+        map[root.0]
+    }
+
+    // Evaluates every node up to the largest root and returns the value of each root.
+    pub(super) fn eval<S: Scalar, const N: usize>(
+        &self,
+        roots: [NodeId; N],
+        at: &[S; 3],
+    ) -> [S; N] {
+        let last = roots.iter().map(|r| r.0).max().unwrap_or_default();
+        let mut vals = Vec::with_capacity(last + 1);
+
+        for node in &self.nodes[..=last] {
+            let val = node.eval(at, |id| vals[id.0]);
+            vals.push(val);
+        }
+
+        roots.map(|r| vals[r.0])
+    }
+
+    // Evaluates roots at many points.
+    // Points are evaluated in batches one node at a time, so each node's work is a tight loop
+    // over the batch which the compiler can vectorize.
+    pub(super) fn eval_many<const N: usize>(
+        &self,
+        roots: [NodeId; N],
+        points: &[[f64; 3]],
+    ) -> Vec<[f64; N]> {
+        let mask = self.reachable_from(roots);
+        let mut vals = vec![[0.0; BATCH]; mask.len()];
+        let mut out = Vec::with_capacity(points.len());
+
+        for batch in points.chunks(BATCH) {
+            for i in (0..mask.len()).filter(|i| mask[*i]) {
+                let mut col = [0.0; BATCH];
+                for (k, at) in batch.iter().enumerate() {
+                    col[k] = self.nodes[i].eval(at, |id| vals[id.0][k]);
+                }
+                vals[i] = col;
+            }
+
+            out.extend((0..batch.len()).map(|k| roots.map(|r| vals[r.0][k])));
+        }
+
+        out
+    }
+
+    // Bounds the value of root over a region given the bounds of each dimension.
+    pub(super) fn eval_interval(&self, root: NodeId, region: &[Interval; 3]) -> Interval {
+        let mut vals = Vec::with_capacity(root.0 + 1);
+
+        for node in &self.nodes[..=root.0] {
+            let val = node.eval_interval(region, |id| vals[id.0]);
+            vals.push(val);
+        }
+
+        vals[root.0]
+    }
+
+    // Adds the derivative of root with respect to wrt to this graph.
+    // Derivatives of shared nodes are only built once.
+    // None is returned if the derivative is zero everywhere.
+    pub(super) fn derivative(&mut self, root: NodeId, wrt: &Dimension) -> Option<NodeId> {
+        let mask = self.reachable(root);
+        let mut derivs: Vec<Option<NodeId>> = vec![None; root.0 + 1];
+
+        for i in 0..=root.0 {
+            if mask[i] {
+                derivs[i] = self.node_derivative(NodeId(i), &derivs, wrt);
+            }
+        }
+
+        derivs[root.0]
+    }
+
+    fn node_derivative(
+        &mut self,
+        id: NodeId,
+        derivs: &[Option<NodeId>],
+        wrt: &Dimension,
+    ) -> Option<NodeId> {
+        let d = |n: &NodeId| derivs[n.0];
+
+        match self.nodes[id.0].clone() {
+            SDFNode::Const(_) => None,
+            SDFNode::Dim(dim) => (dim == *wrt).then(|| self.constant(1.0)),
+            // Floor is piecewise constant.
+            SDFNode::Unary(UnaryOp::Floor, _) => None,
+            SDFNode::Unary(op, a) => {
+                let da = d(&a)?;
+                let outer = match op {
+                    UnaryOp::Neg => return Some(self.unary(UnaryOp::Neg, da)),
+                    // d sqrt(a) = a' / (2 sqrt(a))
+                    UnaryOp::Sqrt => {
+                        let half = self.constant(0.5);
+                        let recip = self.unary(UnaryOp::Recip, id);
+                        self.mul(half, recip)
+                    }
+                    // d |a| = sign(a) a'
+                    UnaryOp::Abs => {
+                        let (zero, one, neg_one) =
+                            (self.constant(0.0), self.constant(1.0), self.constant(-1.0));
+                        self.push(SDFNode::GT {
+                            left: a,
+                            right: zero,
+                            true_val: one,
+                            false_val: neg_one,
+                        })
+                    }
+                    // d 1/a = -a' / a^2
+                    UnaryOp::Recip => {
+                        let sq = self.mul(id, id);
+                        self.unary(UnaryOp::Neg, sq)
+                    }
+                    // d e^a = e^a a'
+                    UnaryOp::Exp => id,
+                    // d ln(a) = a' / a
+                    UnaryOp::Ln => self.unary(UnaryOp::Recip, a),
+                    // d sin(a) = cos(a) a'
+                    UnaryOp::Sin => self.unary(UnaryOp::Cos, a),
+                    // d cos(a) = -sin(a) a'
+                    UnaryOp::Cos => {
+                        let sin = self.unary(UnaryOp::Sin, a);
+                        self.unary(UnaryOp::Neg, sin)
+                    }
+                    UnaryOp::Floor => unreachable!(),
+                };
+
+                Some(self.mul(outer, da))
+            }
+            SDFNode::Binary(op, a, b) => {
+                let (da, db) = (d(&a), d(&b));
+                match op {
+                    BinaryOp::Add => self.sum(da, db),
+                    // d ab = a' b + a b'
+                    BinaryOp::Mul => {
+                        let left = da.map(|da| self.mul(da, b));
+                        let right = db.map(|db| self.mul(a, db));
+                        self.sum(left, right)
+                    }
+                    // d a^b = b a^(b - 1) a' + a^b ln(a) b'
+                    BinaryOp::Pow => {
+                        let base = da.map(|da| {
+                            let neg_one = self.constant(-1.0);
+                            let lowered = self.add(b, neg_one);
+                            let pow = self.binary(BinaryOp::Pow, a, lowered);
+                            let scaled = self.mul(b, pow);
+                            self.mul(scaled, da)
+                        });
+                        let exponent = db.map(|db| {
+                            let ln = self.unary(UnaryOp::Ln, a);
+                            let scaled = self.mul(id, ln);
+                            self.mul(scaled, db)
+                        });
+                        self.sum(base, exponent)
+                    }
+                    // d atan2(a, b) = (b a' - a b') / (a^2 + b^2)
+                    BinaryOp::Atan2 => {
+                        let left = da.map(|da| self.mul(b, da));
+                        let right = db.map(|db| {
+                            let prod = self.mul(a, db);
+                            self.unary(UnaryOp::Neg, prod)
+                        });
+                        let num = self.sum(left, right)?;
+
+                        let (aa, bb) = (self.mul(a, a), self.mul(b, b));
+                        let len_sq = self.add(aa, bb);
+                        let recip = self.unary(UnaryOp::Recip, len_sq);
+                        Some(self.mul(num, recip))
+                    }
+                }
+            }
+            SDFNode::GT {
+                left,
+                right,
+                true_val,
+                false_val,
+            } => {
+                if d(&true_val).is_none() && d(&false_val).is_none() {
+                    return None;
+                }
+
+                let true_val = d(&true_val).unwrap_or_else(|| self.constant(0.0));
+                let false_val = d(&false_val).unwrap_or_else(|| self.constant(0.0));
+                Some(self.push(SDFNode::GT {
+                    left,
+                    right,
+                    true_val,
+                    false_val,
+                }))
+            }
+            // d noise(a, b, c) = noise_x a' + noise_y b' + noise_z c'
+            SDFNode::Noise { seed, order, point } => {
+                let mut total = None;
+                for (i, arg) in point.iter().enumerate() {
+                    let term = d(arg).map(|da| {
+                        let mut order = order;
+                        order[i] += 1;
+                        let partial = self.push(SDFNode::Noise { seed, order, point });
+                        self.mul(partial, da)
+                    });
+                    total = self.sum(total, term);
+                }
+                total
+            }
+        }
+    }
+
+    fn sum(&mut self, a: Option<NodeId>, b: Option<NodeId>) -> Option<NodeId> {
+        match (a, b) {
+            (Some(a), Some(b)) => Some(self.add(a, b)),
+            (a, None) => a,
+            (None, b) => b,
+        }
+    }
+}
@@ -0,0 +1,258 @@
+use crate::{Interval, SDFVolume};
+
+use super::{
+    graph::SDFGraph,
+    node::{BinaryOp, NodeId, SDFNode, UnaryOp},
+    noise::perlin_bound,
+    SDFExpression, SDFGradient,
+};
+
+impl SDFExpression {
+    // Returns a bound on the magnitude of this expression's gradient anywhere in volume.
+    // The value at a point divided by the bound is then never more than the distance from the
+    // point to the surface, as long as the surface is in volume.
+    // The bound comes from interval arithmetic, so it can be loose or infinite but never too small.
+    pub fn lipschitz_bound(&self, volume: &SDFVolume) -> f64 {
+        let region = volume.intervals();
+
+        // Bounding the interval of each derivative is tight away from singularities, but
+        // derivatives of lengths are unbounded wherever the length may be 0.
+        let mut cache = self.grad_cache.lock().unwrap();
+        let SDFGradient { graph, roots, .. } = cache.get_or_insert_with(|| self.derive_grad());
+        let derivative_bound = roots
+            .iter()
+            .map(|root| graph.eval_interval(*root, &region).mag().powi(2))
+            .sum::<f64>()
+            .sqrt();
+
+        derivative_bound.min(self.graph.gradient_bound(self.root, &region))
+    }
+
+    // Scales this expression by the inverse of its Lipschitz bound over volume, so its gradient
+    // magnitude is at most 1 there and it never overestimates the distance to the surface.
+    // Expressions whose bound is zero or not finite are returned unchanged.
+    pub fn normalize(self, volume: &SDFVolume) -> Self {
+        let bound = self.lipschitz_bound(volume);
+        if bound == 0.0 || !bound.is_finite() {
+            return self;
+        }
+
+        self * (1.0 / bound).into()
+    }
+}
+
+// What's known about a node over a region.
+#[derive(Clone, Copy)]
+struct NodeBounds {
+    value: Interval,
+    // A bound on the magnitude of the gradient.
+    grad: f64,
+    // A bit for each axis the node depends on. Gradients of nodes depending on different axes
+    // are orthogonal.
+    axes: u8,
+}
+
+// The gradient bound of a node times a value bounded by mag, where a 0 gradient stays 0.
+fn scaled(grad: f64, mag: f64) -> f64 {
+    if grad == 0.0 {
+        0.0
+    } else {
+        grad * mag
+    }
+}
+
+impl SDFGraph {
+    // Bounds the magnitude of root's gradient over a region by propagating bounds on the
+    // gradient of every node, rather than bounding each derivative separately.
+    fn gradient_bound(&self, root: NodeId, region: &[Interval; 3]) -> f64 {
+        let mut bounds: Vec<NodeBounds> = Vec::with_capacity(root.0 + 1);
+
+        for i in 0..=root.0 {
+            let node = self.node(NodeId(i));
+            let b = |id: &NodeId| bounds[id.0];
+            let orthogonal = |x: &NodeId, y: &NodeId| b(x).axes & b(y).axes == 0;
+
+            let grad = match node {
+                SDFNode::Const(_) => 0.0,
+                SDFNode::Dim(_) => 1.0,
+                SDFNode::Unary(UnaryOp::Sqrt, a) => {
+                    // d sqrt(a) = a' / (2 sqrt(a)), unless a is the squared length of a vector.
+                    let lo = b(a).value.lo;
+                    let outer = if lo > 0.0 {
+                        0.5 / lo.sqrt()
+                    } else {
+                        f64::INFINITY
+                    };
+                    let length = self.length_bound(*a, &bounds).unwrap_or(f64::INFINITY);
+                    scaled(b(a).grad, outer).min(length)
+                }
+                SDFNode::Unary(op, a) => {
+                    let outer = match op {
+                        UnaryOp::Neg | UnaryOp::Abs | UnaryOp::Sin | UnaryOp::Cos => 1.0,
+                        UnaryOp::Floor => 0.0,
+                        UnaryOp::Recip => b(a).value.recip().square().hi,
+                        UnaryOp::Exp => b(a).value.hi.exp(),
+                        UnaryOp::Ln => b(a).value.recip().mag(),
+                        UnaryOp::Sqrt => unreachable!(),
+                    };
+                    scaled(b(a).grad, outer)
+                }
+                SDFNode::Binary(BinaryOp::Add, x, y) => {
+                    combine(b(x).grad, b(y).grad, orthogonal(x, y))
+                }
+                SDFNode::Binary(BinaryOp::Mul, x, y) if x == y => {
+                    scaled(b(x).grad, 2.0 * b(x).value.mag())
+                }
+                SDFNode::Binary(BinaryOp::Mul, x, y) => combine(
+                    scaled(b(x).grad, b(y).value.mag()),
+                    scaled(b(y).grad, b(x).value.mag()),
+                    orthogonal(x, y),
+                ),
+                // Only constant exponents are bounded, d a^e = e a^(e - 1) a'.
+                SDFNode::Binary(BinaryOp::Pow, a, e) => {
+                    let exponent = b(e).value;
+                    if b(e).grad == 0.0 && exponent.lo == exponent.hi {
+                        let outer = b(a).value.pow(Interval::point(exponent.lo - 1.0));
+                        scaled(b(a).grad, exponent.lo.abs() * outer.mag())
+                    } else {
+                        f64::INFINITY
+                    }
+                }
+                // d atan2(y, x) = (x y' - y x') / (x^2 + y^2), which is at most
+                // (|x'| + |y'|) / sqrt(x^2 + y^2).
+                SDFNode::Binary(BinaryOp::Atan2, y, x) => {
+                    let len_sq = b(y).value.square() + b(x).value.square();
+                    scaled(b(y).grad + b(x).grad, 1.0 / len_sq.lo.sqrt())
+                }
+                // Like the derivative, jumps between the branches are ignored.
+                SDFNode::GT {
+                    left,
+                    right,
+                    true_val,
+                    false_val,
+                } => {
+                    let (left, right) = (b(left).value, b(right).value);
+                    if left.lo > right.hi {
+                        b(true_val).grad
+                    } else if left.hi <= right.lo {
+                        b(false_val).grad
+                    } else {
+                        b(true_val).grad.max(b(false_val).grad)
+                    }
+                }
+                // d noise(p) = noise_x p_x' + noise_y p_y' + noise_z p_z'
+                SDFNode::Noise { order, point, .. } => (0..3)
+                    .map(|i| {
+                        let mut order = *order;
+                        order[i] += 1;
+                        scaled(b(&point[i]).grad, perlin_bound(order))
+                    })
+                    .sum(),
+            };
+
+            let value = node.eval_interval(region, |id| bounds[id.0].value);
+            let axes = match node {
+                SDFNode::Dim(d) => 1 << *d as u8,
+                _ => node
+                    .operands()
+                    .iter()
+                    .fold(0, |axes, id| axes | bounds[id.0].axes),
+            };
+            bounds.push(NodeBounds { value, grad, axes });
+        }
+
+        bounds[root.0].grad
+    }
+
+    // Bounds the gradient of sqrt(a) when a is a sum of squares f_i^2, the length of a vector.
+    // The gradient is the sum of f_i / |f| f_i', which is at most the largest |f_i'| when the
+    // f_i depend on different axes and sqrt(sum |f_i'|^2) otherwise.
+    fn length_bound(&self, a: NodeId, bounds: &[NodeBounds]) -> Option<f64> {
+        let mut terms = Vec::new();
+        let mut pending = vec![a];
+        while let Some(id) = pending.pop() {
+            match self.node(id) {
+                SDFNode::Binary(BinaryOp::Add, l, r) => pending.extend([*l, *r]),
+                SDFNode::Binary(BinaryOp::Mul, l, r) if l == r => terms.push(bounds[l.0]),
+                _ => return None,
+            }
+        }
+
+        let mut axes = 0;
+        let mut disjoint = true;
+        for term in terms.iter() {
+            disjoint &= axes & term.axes == 0;
+            axes |= term.axes;
+        }
+
+        Some(if disjoint {
+            terms.iter().map(|t| t.grad).fold(0.0, f64::max)
+        } else {
+            terms.iter().map(|t| t.grad.powi(2)).sum::<f64>().sqrt()
+        })
+    }
+}
+
+// The gradient bound of a sum of terms with the given gradient bounds.
+fn combine(a: f64, b: f64, orthogonal: bool) -> f64 {
+    if orthogonal {
+        a.hypot(b)
+    } else {
+        a + b
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+
+    use crate::{SDFExpression, SDFVolume, VolumetricFunc};
+
+    #[test]
+    fn lipschitz_bounds_contain_gradients() {
+        let volume = SDFVolume {
+            base: Vector3::new(-1.0, -1.0, -1.0),
+            size: Vector3::new(2.0, 2.0, 2.0),
+        };
+        let (x, y, z) = (SDFExpression::x(), SDFExpression::y(), SDFExpression::z());
+        let squared = x.clone() * x + y.clone() * y + z.clone() * z - 0.25.into();
+        let bound = squared.lipschitz_bound(&volume);
+        assert!((bound - 12.0f64.sqrt()).abs() < 1e-12);
+
+        let normalized = squared.clone().normalize(&volume);
+        let torus = SDFExpression::torus(Vector3::zeros(), 0.6, 0.2);
+        for i in 0..100 {
+            let t = i as f64 * 0.02 - 1.0;
+            let at = Vector3::new(t, (3.0 * t).sin(), t * t - 0.5);
+            assert!(normalized.grad(&at).norm() <= 1.0 + 1e-12);
+            assert!(torus.grad(&at).norm() <= torus.lipschitz_bound(&volume) + 1e-12);
+        }
+
+        // A point 0.25 outside the surface is never reported as further away.
+        let at = Vector3::new(0.75, 0.0, 0.0);
+        assert!(normalized.eval(&at) <= 0.25);
+    }
+
+    #[test]
+    fn distances_have_tight_bounds() {
+        // The volume contains the centers and axes of the shapes, where lengths are 0.
+        let volume = SDFVolume {
+            base: Vector3::new(-1.0, -1.0, -1.0),
+            size: Vector3::new(2.0, 2.0, 2.0),
+        };
+        let sphere = SDFExpression::sphere(Vector3::new(0.1, 0.0, -0.2), 0.5);
+        let torus = SDFExpression::torus(Vector3::zeros(), 0.6, 0.2);
+        for shape in [&sphere, &torus] {
+            assert!((shape.lipschitz_bound(&volume) - 1.0).abs() < 1e-12);
+        }
+
+        let cuboid = SDFExpression::cuboid(Vector3::zeros(), Vector3::new(0.5, 0.3, 0.2));
+        assert!(cuboid.lipschitz_bound(&volume) <= 2.0);
+
+        // Normalizing a scaled distance recovers the distance.
+        let scaled = sphere.clone() * 3.0.into();
+        let at = Vector3::new(0.4, 0.7, 0.2);
+        let normalized = scaled.normalize(&volume);
+        assert!((normalized.eval(&at) - sphere.eval(&at)).abs() < 1e-12);
+    }
+}
@@ -1,69 +1,239 @@
-mod prod;
-use prod::SDFExprProd;
+mod blend;
 
-mod sop;
-use sop::SDFExprSOP;
+mod domain;
 
-mod term;
-use term::SDFExprTerm;
+mod graph;
+use graph::SDFGraph;
 
-use crate::{Dimension, VolumetricFunc};
-use nalgebra::Vector3;
+mod lipschitz;
+
+mod node;
+use node::{BinaryOp, NodeId, SDFNode, UnaryOp};
+
+mod noise;
+
+mod offset;
+
+mod parse;
+pub use parse::ParseError;
+
+mod primitives;
+
+mod profile;
+pub use profile::SDFProfile;
+
+#[cfg(feature = "serde")]
+mod serialize;
+
+mod simplify;
+
+mod tape;
+pub use tape::SDFTape;
+
+mod transform;
+
+use crate::{Dimension, Dual, Interval, SDFVolume, Scalar, ScalarFunc, VolumetricFunc};
+use nalgebra::{Matrix3, Vector3};
 use std::{
-    ops::{Add, Mul, Neg, Sub},
+    ops::{Add, Div, Mul, Neg, Sub},
     sync::{Arc, Mutex},
 };
 
-#[derive(Clone, Default)]
+// An SDFExpression is a function of position built from constants, dimensions and operations on them.
+// Expressions are stored as a graph of shared nodes, so cloning an expression and reusing it in
+// several places doesn't duplicate it.
+#[derive(Clone)]
 pub struct SDFExpression {
-    sops: SDFExprSOP,
-    grad_cache: Arc<Mutex<Option<[SDFExprSOP; 3]>>>,
+    graph: Arc<SDFGraph>,
+    root: NodeId,
+    grad_cache: Arc<Mutex<Option<SDFGradient>>>,
+    hessian_cache: Arc<Mutex<Option<SDFHessian>>>,
+}
+
+// The derivatives of an expression in each dimension, sharing a single graph with the expression.
+struct SDFGradient {
+    graph: SDFGraph,
+    value: NodeId,
+    roots: [NodeId; 3],
+}
+
+// The second derivatives of an expression, the upper triangle of the hessian row by row.
+struct SDFHessian {
+    graph: SDFGraph,
+    roots: [NodeId; 6],
 }
 
 impl VolumetricFunc for SDFExpression {
     fn eval(&self, at: &nalgebra::Vector3<f64>) -> f64 {
-        self.sops.eval(at)
+        self.eval_scalar(&(*at).into())
     }
 
     fn grad(&self, at: &nalgebra::Vector3<f64>) -> Vector3<f64> {
         let mut cache = self.grad_cache.lock().unwrap();
-        let [x, y, z] = cache.get_or_insert_with(|| self.derive_grad());
+        let SDFGradient { graph, roots, .. } = cache.get_or_insert_with(|| self.derive_grad());
+
+        graph.eval(*roots, &(*at).into()).into()
+    }
+
+    // The gradient is computed with Duals instead of the symbolic derivative.
+    fn eval_with_grad(&self, at: &Vector3<f64>) -> (f64, Vector3<f64>) {
+        let Dual { val, grad } = self.eval_scalar(&Dual::position(at));
+        (val, grad)
+    }
+
+    fn eval_many(&self, points: &[Vector3<f64>]) -> Vec<f64> {
+        let points: Vec<[f64; 3]> = points.iter().map(|p| (*p).into()).collect();
+        let vals = self.graph.eval_many([self.root], &points);
+        vals.into_iter().map(|[val]| val).collect()
+    }
+
+    fn grad_many(&self, points: &[Vector3<f64>]) -> Vec<Vector3<f64>> {
+        let mut cache = self.grad_cache.lock().unwrap();
+        let SDFGradient { graph, roots, .. } = cache.get_or_insert_with(|| self.derive_grad());
+
+        let points: Vec<[f64; 3]> = points.iter().map(|p| (*p).into()).collect();
+        let grads = graph.eval_many(*roots, &points);
+        grads.into_iter().map(Vector3::from).collect()
+    }
 
-        Vector3::new(x.eval(at), y.eval(at), z.eval(at))
+    fn hessian(&self, at: &Vector3<f64>) -> Matrix3<f64> {
+        let mut cache = self.hessian_cache.lock().unwrap();
+        let SDFHessian { graph, roots } = cache.get_or_insert_with(|| self.derive_hessian());
+
+        let [xx, xy, xz, yy, yz, zz] = graph.eval(*roots, &(*at).into());
+        Matrix3::new(xx, xy, xz, xy, yy, yz, xz, yz, zz)
+    }
+
+    fn eval_interval(&self, volume: &SDFVolume) -> Option<Interval> {
+        Some(self.graph.eval_interval(self.root, &volume.intervals()))
+    }
+}
+
+impl ScalarFunc for SDFExpression {
+    fn eval_scalar<S: Scalar>(&self, at: &[S; 3]) -> S {
+        let [val] = self.graph.eval([self.root], at);
+        val
     }
 }
 
 impl SDFExpression {
-    fn derive_grad(&self) -> [SDFExprSOP; 3] {
-        [
-            self.sops.derivative(&super::Dimension::X),
-            self.sops.derivative(&super::Dimension::Y),
-            self.sops.derivative(&super::Dimension::Z),
-        ]
+    fn derive_grad(&self) -> SDFGradient {
+        let mut derivatives = (*self.graph).clone();
+        let roots = [Dimension::X, Dimension::Y, Dimension::Z].map(|d| {
+            match derivatives.derivative(self.root, &d) {
+                Some(root) => root,
+                None => derivatives.constant(0.0),
+            }
+        });
+
+        // The value is copied unchanged so it evaluates exactly like the expression,
+        // only the derivatives are simplified.
+        let mut graph = SDFGraph::default();
+        let value = graph.import(&self.graph, self.root);
+        let roots = graph.simplify(&derivatives, roots);
+
+        SDFGradient {
+            graph,
+            value,
+            roots,
+        }
+    }
+
+    // Differentiates the simplified gradient again.
+    fn derive_hessian(&self) -> SDFHessian {
+        let SDFGradient {
+            graph: mut derivatives,
+            roots: first,
+            ..
+        } = self.derive_grad();
+
+        let dims = [Dimension::X, Dimension::Y, Dimension::Z];
+        let roots =
+            [(0, 0), (0, 1), (0, 2), (1, 1), (1, 2), (2, 2)].map(|(i, j)| {
+                match derivatives.derivative(first[i], &dims[j]) {
+                    Some(root) => root,
+                    None => derivatives.constant(0.0),
+                }
+            });
+
+        let mut graph = SDFGraph::default();
+        let roots = graph.simplify(&derivatives, roots);
+        SDFHessian { graph, roots }
     }
 
     pub fn max(a: Self, b: Self) -> Self {
-        let arc = Arc::new(a.sops);
-        let brc = Arc::new(b.sops);
-        let term = SDFExprTerm::GT {
-            left: arc.clone(),
-            right: brc.clone(),
-            true_val: arc,
-            false_val: brc,
-        };
-        SDFExprSOP::from(SDFExprProd::from(term)).into()
+        Self::combine([a, b], |graph, [a, b]| {
+            graph.push(SDFNode::GT {
+                left: a,
+                right: b,
+                true_val: a,
+                false_val: b,
+            })
+        })
     }
 
     pub fn min(a: Self, b: Self) -> Self {
-        let arc = Arc::new(a.sops);
-        let brc = Arc::new(b.sops);
-        let term = SDFExprTerm::GT {
-            left: arc.clone(),
-            right: brc.clone(),
-            true_val: brc,
-            false_val: arc,
-        };
-        SDFExprSOP::from(SDFExprProd::from(term)).into()
+        Self::combine([a, b], |graph, [a, b]| {
+            graph.push(SDFNode::GT {
+                left: a,
+                right: b,
+                true_val: b,
+                false_val: a,
+            })
+        })
+    }
+
+    // Returns true_val where left > right and false_val elsewhere.
+    fn select(left: Self, right: Self, true_val: Self, false_val: Self) -> Self {
+        Self::combine([left, right, true_val, false_val], |graph, [l, r, t, f]| {
+            graph.push(SDFNode::GT {
+                left: l,
+                right: r,
+                true_val: t,
+                false_val: f,
+            })
+        })
+    }
+
+    pub fn sqrt(self) -> Self {
+        self.unary(UnaryOp::Sqrt)
+    }
+
+    pub fn abs(self) -> Self {
+        self.unary(UnaryOp::Abs)
+    }
+
+    pub fn recip(self) -> Self {
+        self.unary(UnaryOp::Recip)
+    }
+
+    pub fn pow(self, exponent: Self) -> Self {
+        self.binary(BinaryOp::Pow, exponent)
+    }
+
+    pub fn exp(self) -> Self {
+        self.unary(UnaryOp::Exp)
+    }
+
+    pub fn ln(self) -> Self {
+        self.unary(UnaryOp::Ln)
+    }
+
+    pub fn sin(self) -> Self {
+        self.unary(UnaryOp::Sin)
+    }
+
+    pub fn cos(self) -> Self {
+        self.unary(UnaryOp::Cos)
+    }
+
+    pub fn floor(self) -> Self {
+        self.unary(UnaryOp::Floor)
+    }
+
+    // The four quadrant arctangent of self / x, matching f64::atan2.
+    pub fn atan2(self, x: Self) -> Self {
+        self.binary(BinaryOp::Atan2, x)
     }
 
     pub fn x() -> Self {
@@ -77,13 +247,68 @@ impl SDFExpression {
     pub fn z() -> Self {
         Dimension::Z.into()
     }
+
+    // Returns this expression with x, y and z replaced by the corresponding expressions in dims.
+    fn substitute(&self, dims: [Self; 3]) -> Self {
+        Self::combine(dims, |graph, dims| {
+            graph.substitute(&self.graph, self.root, dims)
+        })
+    }
+
+    fn unary(self, op: UnaryOp) -> Self {
+        Self::combine([self], |graph, [a]| graph.unary(op, a))
+    }
+
+    fn binary(self, op: BinaryOp, rhs: Self) -> Self {
+        Self::combine([self, rhs], |graph, [a, b]| graph.binary(op, a, b))
+    }
+
+    // Merges the graphs of exprs and adds the nodes returned by build, given the root of each expression.
+    // The first expression's graph is reused when no other expression refers to it.
+    fn combine<const N: usize, F>(exprs: [Self; N], build: F) -> Self
+    where
+        F: FnOnce(&mut SDFGraph, [NodeId; N]) -> NodeId,
+    {
+        let mut graph: Option<Arc<SDFGraph>> = None;
+        let mut roots = [NodeId(0); N];
+
+        for (i, expr) in exprs.into_iter().enumerate() {
+            roots[i] = match &mut graph {
+                None => {
+                    graph = Some(expr.graph);
+                    expr.root
+                }
+                Some(graph) if Arc::ptr_eq(graph, &expr.graph) => expr.root,
+                Some(graph) => Arc::make_mut(graph).import(&expr.graph, expr.root),
+            };
+        }
+
+        let mut graph = graph.unwrap_or_default();
+        let root = build(Arc::make_mut(&mut graph), roots);
+        Self::new(graph, root)
+    }
+
+    fn new(graph: Arc<SDFGraph>, root: NodeId) -> Self {
+        Self {
+            graph,
+            root,
+            grad_cache: Arc::new(Mutex::default()),
+            hessian_cache: Arc::new(Mutex::default()),
+        }
+    }
+}
+
+impl Default for SDFExpression {
+    fn default() -> Self {
+        0.0.into()
+    }
 }
 
 impl Add<Self> for SDFExpression {
     type Output = Self;
 
     fn add(self, rhs: Self) -> Self::Output {
-        (self.sops + rhs.sops).into()
+        self.binary(BinaryOp::Add, rhs)
     }
 }
 
@@ -91,7 +316,7 @@ impl Neg for SDFExpression {
     type Output = Self;
 
     fn neg(self) -> Self::Output {
-        (-self.sops).into()
+        self.unary(UnaryOp::Neg)
     }
 }
 
@@ -107,7 +332,15 @@ impl Mul<Self> for SDFExpression {
     type Output = Self;
 
     fn mul(self, rhs: Self) -> Self::Output {
-        (self.sops * rhs.sops).into()
+        self.binary(BinaryOp::Mul, rhs)
+    }
+}
+
+impl Div for SDFExpression {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        self.mul(rhs.recip())
     }
 }
 
@@ -116,21 +349,85 @@ where
     T: Into<f64>,
 {
     fn from(value: T) -> Self {
-        SDFExprSOP::from(value).into()
+        SDFNode::Const(value.into()).into()
     }
 }
 
 impl From<Dimension> for SDFExpression {
     fn from(value: Dimension) -> Self {
-        SDFExprSOP::from(value).into()
+        SDFNode::Dim(value).into()
     }
 }
 
-impl From<SDFExprSOP> for SDFExpression {
-    fn from(value: SDFExprSOP) -> Self {
-        Self {
-            sops: value,
-            grad_cache: Arc::new(Mutex::default()),
+impl From<SDFNode> for SDFExpression {
+    fn from(value: SDFNode) -> Self {
+        let mut graph = SDFGraph::default();
+        let root = graph.push(value);
+        Self::new(Arc::new(graph), root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+
+    use crate::{SDFExpression, VolumetricFunc};
+
+    #[test]
+    fn function_gradients() {
+        let (x, y, z) = (SDFExpression::x(), SDFExpression::y(), SDFExpression::z());
+        let exprs = [
+            (x.clone() * x.clone() + y.clone() * y.clone()).sqrt(),
+            (x.clone() - z.clone()).abs(),
+            y.clone() / (z.clone() + 2.0.into()),
+            x.clone().pow(y.clone() + 2.0.into()),
+            (x.clone() * y.clone()).exp(),
+            (z.clone() * z.clone() + 1.0.into()).ln(),
+            (x.clone() * 3.0.into()).sin() * y.clone().cos(),
+            y.clone().atan2(x.clone() - z.clone()),
+        ];
+
+        let at = Vector3::new(0.8, 0.6, -0.3);
+        let h = 1e-6;
+        for expr in &exprs {
+            let grad = expr.grad(&at);
+            let (_, dual_grad) = expr.eval_with_grad(&at);
+            assert!((grad - dual_grad).norm() < 1e-12);
+            for i in 0..3 {
+                let mut offset = Vector3::zeros();
+                offset[i] = h;
+                let fd = (expr.eval(&(at + offset)) - expr.eval(&(at - offset))) / (2.0 * h);
+                assert!((grad[i] - fd).abs() < 1e-4, "{} != {}", grad[i], fd);
+
+                let fd = (expr.grad(&(at + offset)) - expr.grad(&(at - offset))) / (2.0 * h);
+                let hessian = expr.hessian(&at);
+                assert!((hessian.column(i) - fd).norm() < 1e-4);
+            }
         }
+
+        // Batches are evaluated in chunks, so use more points than fit in one.
+        let points: Vec<_> = (0..100)
+            .map(|i| at + Vector3::new(0.01, -0.02, 0.005) * i as f64)
+            .collect();
+        for expr in &exprs {
+            let vals = expr.eval_many(&points);
+            let grads = expr.grad_many(&points);
+            for (k, at) in points.iter().enumerate() {
+                assert_eq!(vals[k], expr.eval(at));
+                assert_eq!(grads[k], expr.grad(at));
+            }
+        }
+    }
+
+    #[test]
+    fn shared_subexpressions_are_not_expanded() {
+        // Expanding this as a sum of products would take 2^40 terms.
+        let mut expr = SDFExpression::x() + 1.0.into();
+        for _ in 0..40 {
+            expr = expr.clone() * expr;
+        }
+
+        assert_eq!(expr.eval(&Vector3::zeros()), 1.0);
+        assert_eq!(expr.grad(&Vector3::zeros()).x, 2.0f64.powi(40));
     }
 }
@@ -0,0 +1,283 @@
+use std::hash::{Hash, Hasher};
+
+use crate::data::{Dimension, Interval, Scalar};
+
+use super::noise::{perlin, perlin_bound};
+
+// The index of a node in an SDFGraph.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub(super) struct NodeId(pub(super) usize);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(super) enum UnaryOp {
+    Neg,
+    Sqrt,
+    Abs,
+    Recip,
+    Exp,
+    Ln,
+    Sin,
+    Cos,
+    Floor,
+}
+
+impl UnaryOp {
+    pub(super) fn apply<S: Scalar>(&self, a: S) -> S {
+        match self {
+            UnaryOp::Neg => -a,
+            UnaryOp::Sqrt => a.sqrt(),
+            UnaryOp::Abs => a.abs(),
+            UnaryOp::Recip => a.recip(),
+            UnaryOp::Exp => a.exp(),
+            UnaryOp::Ln => a.ln(),
+            UnaryOp::Sin => a.sin(),
+            UnaryOp::Cos => a.cos(),
+            UnaryOp::Floor => a.floor(),
+        }
+    }
+
+    pub(super) fn apply_interval(&self, a: Interval) -> Interval {
+        match self {
+            UnaryOp::Neg => -a,
+            UnaryOp::Sqrt => a.sqrt(),
+            UnaryOp::Abs => a.abs(),
+            UnaryOp::Recip => a.recip(),
+            UnaryOp::Exp => a.exp(),
+            UnaryOp::Ln => a.ln(),
+            UnaryOp::Sin => a.sin(),
+            UnaryOp::Cos => a.cos(),
+            UnaryOp::Floor => a.floor(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(super) enum BinaryOp {
+    Add,
+    Mul,
+    Pow,
+    Atan2,
+}
+
+impl BinaryOp {
+    pub(super) fn apply<S: Scalar>(&self, a: S, b: S) -> S {
+        match self {
+            BinaryOp::Add => a + b,
+            BinaryOp::Mul => a * b,
+            BinaryOp::Pow => a.pow(b),
+            BinaryOp::Atan2 => a.atan2(b),
+        }
+    }
+
+    pub(super) fn apply_interval(&self, a: Interval, b: Interval) -> Interval {
+        match self {
+            BinaryOp::Add => a + b,
+            BinaryOp::Mul => a * b,
+            BinaryOp::Pow => a.pow(b),
+            BinaryOp::Atan2 => a.atan2(b),
+        }
+    }
+}
+
+// A single operation in an SDFGraph.
+// Operands always refer to nodes earlier in the graph.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(super) enum SDFNode {
+    Const(#[cfg_attr(feature = "serde", serde(with = "super::serialize::constant"))] f64),
+    Dim(Dimension),
+    Unary(UnaryOp, NodeId),
+    Binary(BinaryOp, NodeId, NodeId),
+    GT {
+        left: NodeId,
+        right: NodeId,
+        true_val: NodeId,
+        false_val: NodeId,
+    },
+    // Gradient noise of point, differentiated order[i] times along each axis.
+    Noise {
+        seed: u32,
+        order: [u8; 3],
+        point: [NodeId; 3],
+    },
+}
+
+impl SDFNode {
+    // Evaluates this node at a position given a function returning the value of each operand.
+    pub(super) fn eval<S, F>(&self, at: &[S; 3], val: F) -> S
+    where
+        S: Scalar,
+        F: Fn(NodeId) -> S,
+    {
+        match self {
+            SDFNode::Const(c) => (*c).into(),
+            SDFNode::Dim(d) => at[*d as usize],
+            SDFNode::Unary(op, a) => op.apply(val(*a)),
+            SDFNode::Binary(op, a, b) => op.apply(val(*a), val(*b)),
+            SDFNode::GT {
+                left,
+                right,
+                true_val,
+                false_val,
+            } => {
+                if val(*left).value() > val(*right).value() {
+                    val(*true_val)
+                } else {
+                    val(*false_val)
+                }
+            }
+            SDFNode::Noise { seed, order, point } => {
+                let args = point.map(&val);
+                let vals = args.map(|a| a.value());
+                let partial = |i: usize| {
+                    let mut order = *order;
+                    order[i] += 1;
+                    perlin(*seed, order, vals)
+                };
+                S::from_partials(perlin(*seed, *order, vals), partial, &args)
+            }
+        }
+    }
+
+    // Bounds this node over a region given a function returning the bounds of each operand.
+    pub(super) fn eval_interval<F>(&self, region: &[Interval; 3], val: F) -> Interval
+    where
+        F: Fn(NodeId) -> Interval,
+    {
+        match self {
+            SDFNode::Const(c) => Interval::point(*c),
+            SDFNode::Dim(d) => region[*d as usize],
+            SDFNode::Unary(op, a) => op.apply_interval(val(*a)),
+            SDFNode::Binary(BinaryOp::Mul, a, b) if a == b => val(*a).square(),
+            SDFNode::Binary(op, a, b) => op.apply_interval(val(*a), val(*b)),
+            SDFNode::GT {
+                left,
+                right,
+                true_val,
+                false_val,
+            } => Interval::select(val(*left), val(*right), val(*true_val), val(*false_val)),
+            SDFNode::Noise { order, .. } => {
+                let bound = perlin_bound(*order);
+                Interval::new(-bound, bound)
+            }
+        }
+    }
+
+    // Returns a copy of this node with each operand replaced by map(operand).
+    pub(super) fn map_operands<F>(&self, mut map: F) -> Self
+    where
+        F: FnMut(NodeId) -> NodeId,
+    {
+        match self {
+            SDFNode::Const(_) | SDFNode::Dim(_) => self.clone(),
+            SDFNode::Unary(op, a) => SDFNode::Unary(*op, map(*a)),
+            SDFNode::Binary(op, a, b) => SDFNode::Binary(*op, map(*a), map(*b)),
+            SDFNode::GT {
+                left,
+                right,
+                true_val,
+                false_val,
+            } => SDFNode::GT {
+                left: map(*left),
+                right: map(*right),
+                true_val: map(*true_val),
+                false_val: map(*false_val),
+            },
+            SDFNode::Noise { seed, order, point } => SDFNode::Noise {
+                seed: *seed,
+                order: *order,
+                point: point.map(map),
+            },
+        }
+    }
+
+    pub(super) fn operands(&self) -> Vec<NodeId> {
+        let mut operands = Vec::new();
+        self.map_operands(|id| {
+            operands.push(id);
+            id
+        });
+        operands
+    }
+}
+
+// Nodes are compared structurally so identical nodes can be shared.
+// Constants are compared by their bit patterns so NaN constants can still be shared.
+impl PartialEq for SDFNode {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (SDFNode::Const(a), SDFNode::Const(b)) => a.to_bits() == b.to_bits(),
+            (SDFNode::Dim(a), SDFNode::Dim(b)) => a == b,
+            (SDFNode::Unary(op_a, a), SDFNode::Unary(op_b, b)) => op_a == op_b && a == b,
+            (SDFNode::Binary(op_a, a0, a1), SDFNode::Binary(op_b, b0, b1)) => {
+                op_a == op_b && a0 == b0 && a1 == b1
+            }
+            (
+                SDFNode::GT {
+                    left: al,
+                    right: ar,
+                    true_val: at,
+                    false_val: af,
+                },
+                SDFNode::GT {
+                    left: bl,
+                    right: br,
+                    true_val: bt,
+                    false_val: bf,
+                },
+            ) => al == bl && ar == br && at == bt && af == bf,
+            (
+                SDFNode::Noise {
+                    seed: seed_a,
+                    order: order_a,
+                    point: a,
+                },
+                SDFNode::Noise {
+                    seed: seed_b,
+                    order: order_b,
+                    point: b,
+                },
+            ) => seed_a == seed_b && order_a == order_b && a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for SDFNode {}
+
+impl Hash for SDFNode {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            SDFNode::Const(c) => c.to_bits().hash(state),
+            SDFNode::Dim(d) => d.hash(state),
+            SDFNode::Unary(op, a) => {
+                op.hash(state);
+                a.hash(state);
+            }
+            SDFNode::Binary(op, a, b) => {
+                op.hash(state);
+                a.hash(state);
+                b.hash(state);
+            }
+            SDFNode::GT {
+                left,
+                right,
+                true_val,
+                false_val,
+            } => [left, right, true_val, false_val].hash(state),
+            SDFNode::Noise { seed, order, point } => {
+                seed.hash(state);
+                order.hash(state);
+                point.hash(state);
+            }
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use super::{SDFExpression, SDFNode};
+
+// Perlin's improved gradient noise, evaluated from a hash of each lattice cell so it covers all
+// of space without a permutation table. Derivatives of any order are computed analytically,
+// order[i] being the number of times the noise is differentiated along axis i.
+
+// The gradients at lattice points, the midpoints of a cube's edges.
+const GRADIENTS: [[f64; 3]; 12] = [
+    [1.0, 1.0, 0.0],
+    [-1.0, 1.0, 0.0],
+    [1.0, -1.0, 0.0],
+    [-1.0, -1.0, 0.0],
+    [1.0, 0.0, 1.0],
+    [-1.0, 0.0, 1.0],
+    [1.0, 0.0, -1.0],
+    [-1.0, 0.0, -1.0],
+    [0.0, 1.0, 1.0],
+    [0.0, -1.0, 1.0],
+    [0.0, 1.0, -1.0],
+    [0.0, -1.0, -1.0],
+];
+
+// The largest absolute value of each derivative of fade between 0 and 1.
+const FADE_MAX: [f64; 6] = [1.0, 1.875, 5.78, 60.0, 360.0, 720.0];
+
+fn hash(seed: u32, cell: [i64; 3]) -> usize {
+    let mut h = seed as u64 ^ 0x9e37_79b9_7f4a_7c15;
+    for c in cell {
+        h = (h ^ c as u64).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        h ^= h >> 31;
+    }
+    (h >> 32) as usize
+}
+
+// The nth derivative of the quintic curve 6t^5 - 15t^4 + 10t^3.
+fn fade(t: f64, n: u8) -> f64 {
+    match n {
+        0 => t * t * t * (t * (t * 6.0 - 15.0) + 10.0),
+        1 => 30.0 * t * t * (t - 1.0) * (t - 1.0),
+        2 => 60.0 * t * (2.0 * t * t - 3.0 * t + 1.0),
+        3 => 60.0 * (6.0 * t * t - 6.0 * t + 1.0),
+        4 => 360.0 * (2.0 * t - 1.0),
+        5 => 720.0,
+        _ => 0.0,
+    }
+}
+
+// The nth derivative of the weight of a corner along one axis, t being the offset from the
+// low side of the cell.
+fn weight(t: f64, high: bool, n: u8) -> f64 {
+    match (high, n) {
+        (true, _) => fade(t, n),
+        (false, 0) => 1.0 - fade(t, 0),
+        (false, _) => -fade(t, n),
+    }
+}
+
+pub(super) fn perlin(seed: u32, order: [u8; 3], at: [f64; 3]) -> f64 {
+    let cell = at.map(f64::floor);
+    let t = [0, 1, 2].map(|i| at[i] - cell[i]);
+
+    let mut sum = 0.0;
+    for corner in 0..8 {
+        let high = [0, 1, 2].map(|i| corner >> i & 1 == 1);
+        let gradient =
+            GRADIENTS[hash(seed, [0, 1, 2].map(|i| cell[i] as i64 + high[i] as i64)) % 12];
+        let weights = [0, 1, 2].map(|i| weight(t[i], high[i], order[i]));
+
+        // Each corner adds gradient . offset * weight, which is a sum of products of functions
+        // of a single axis, so derivatives are taken one axis at a time.
+        for axis in (0..3).filter(|a| gradient[*a] != 0.0) {
+            let offset = if high[axis] { t[axis] - 1.0 } else { t[axis] };
+            let n = order[axis];
+            let mut along = offset * weights[axis];
+            if n > 0 {
+                along += n as f64 * weight(t[axis], high[axis], n - 1);
+            }
+
+            sum += gradient[axis] * along * weights[(axis + 1) % 3] * weights[(axis + 2) % 3];
+        }
+    }
+
+    sum
+}
+
+// A bound on the absolute value of perlin with a given order of derivative.
+pub(super) fn perlin_bound(order: [u8; 3]) -> f64 {
+    // The weights are positive and sum to 1, so the noise is bounded by the largest
+    // gradient . offset, sqrt(2) sqrt(3).
+    if order == [0; 3] {
+        return 2.45;
+    }
+
+    let fade_max = |n: u8| FADE_MAX.get(n as usize).copied().unwrap_or(0.0);
+    let weight_max = |n: u8| if n == 0 { 1.0 } else { fade_max(n) };
+    let along_max = |n: u8| match n {
+        0 => 1.0,
+        _ => fade_max(n) + n as f64 * weight_max(n - 1),
+    };
+
+    // Every corner has two nonzero gradient components.
+    let max_term = (0..3)
+        .map(|axis| {
+            along_max(order[axis])
+                * weight_max(order[(axis + 1) % 3])
+                * weight_max(order[(axis + 2) % 3])
+        })
+        .fold(0.0, f64::max);
+    16.0 * max_term
+}
+
+impl SDFExpression {
+    // Gradient noise of point, roughly between -1 and 1 and varying over a distance of about 1.
+    // Different seeds give unrelated noise.
+    pub fn noise(point: [Self; 3], seed: u32) -> Self {
+        Self::combine(point, |graph, point| {
+            graph.push(SDFNode::Noise {
+                seed,
+                order: [0; 3],
+                point,
+            })
+        })
+    }
+
+    // Fractal noise, the sum of octaves of noise of point.
+    // Each octave's frequency is multiplied by lacunarity and its amplitude by gain.
+    pub fn fbm(point: [Self; 3], seed: u32, octaves: usize, lacunarity: f64, gain: f64) -> Self {
+        let mut sum = Self::default();
+        let (mut frequency, mut amplitude) = (1.0, 1.0);
+        for octave in 0..octaves {
+            let scaled = point.clone().map(|p| p * frequency.into());
+            sum = sum + Self::noise(scaled, seed.wrapping_add(octave as u32)) * amplitude.into();
+            frequency *= lacunarity;
+            amplitude *= gain;
+        }
+
+        sum
+    }
+
+    // Adds noise of the position scaled by frequency to this expression.
+    // The result is no longer an exact distance, so amplitude * frequency should stay small.
+    pub fn displace(self, amplitude: f64, frequency: f64, seed: u32) -> Self {
+        let point = [Self::x(), Self::y(), Self::z()].map(|p| p * frequency.into());
+        self + Self::noise(point, seed) * amplitude.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+
+    use crate::{SDFExpression, SDFVolume, VolumetricFunc};
+
+    use super::{perlin, perlin_bound};
+
+    #[test]
+    fn noise_derivatives_and_bounds() {
+        let (x, y, z) = (SDFExpression::x(), SDFExpression::y(), SDFExpression::z());
+        let noise = SDFExpression::fbm([x, y.clone() * 2.0.into(), z + y], 7, 3, 2.0, 0.5);
+        let at = Vector3::new(0.3, -1.7, 2.2);
+        let h = 1e-6;
+
+        let grad = noise.grad(&at);
+        let (_, dual_grad) = noise.eval_with_grad(&at);
+        for i in 0..3 {
+            let offset = Vector3::ith(i, h);
+            let fd = (noise.eval(&(at + offset)) - noise.eval(&(at - offset))) / (2.0 * h);
+            assert!((grad[i] - fd).abs() < 1e-4, "{} != {}", grad[i], fd);
+            assert!((dual_grad[i] - fd).abs() < 1e-4);
+        }
+
+        // Second derivatives are smooth too.
+        let p = [0.4, 1.3, -0.8];
+        let d_xy = |x: f64| perlin(1, [0, 1, 0], [x, p[1], p[2]]);
+        let fd = (d_xy(p[0] + h) - d_xy(p[0] - h)) / (2.0 * h);
+        assert!((perlin(1, [1, 1, 0], p) - fd).abs() < 1e-4);
+
+        for i in 0..1000 {
+            let p = [i as f64 * 0.137, i as f64 * -0.071, i as f64 * 0.013];
+            for order in [[0, 0, 0], [1, 0, 0], [0, 2, 1]] {
+                assert!(perlin(3, order, p).abs() <= perlin_bound(order));
+            }
+        }
+
+        let volume = SDFVolume {
+            base: Vector3::new(-1.0, -1.0, -1.0),
+            size: Vector3::new(2.0, 2.0, 2.0),
+        };
+        let bounds = noise.eval_interval(&volume).unwrap();
+        assert!(bounds.contains(noise.eval(&at.scale(0.1))));
+    }
+}
@@ -0,0 +1,76 @@
+use super::SDFExpression;
+
+// Operators moving the surface along its normal.
+// These keep distances exact wherever the original expression is an exact distance.
+impl SDFExpression {
+    // Moves the surface outwards by distance, or inwards if distance is negative.
+    pub fn offset(self, distance: f64) -> Self {
+        self - distance.into()
+    }
+
+    // Hollows out the shape, leaving a wall of thickness inside the original surface.
+    pub fn shell(self, thickness: f64) -> Self {
+        Self::max(self.clone(), -(self + thickness.into()))
+    }
+
+    // Replaces the surface with a wall of thickness centered on it.
+    // Each further layer splits every wall into two walls half as thick, separated by a gap as
+    // wide as each of them, giving 2^(layers - 1) walls.
+    pub fn onion(self, thickness: f64, layers: usize) -> Self {
+        let mut expr = self;
+        let mut half_thickness = 0.5 * thickness;
+        for _ in 0..layers {
+            expr = expr.abs() - half_thickness.into();
+            half_thickness *= 0.5;
+        }
+
+        expr
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+
+    use crate::{SDFExpression, VolumetricFunc};
+
+    #[test]
+    fn offset_surfaces_keep_distances() {
+        let sphere = SDFExpression::sphere(Vector3::zeros(), 2.0);
+        let offset = sphere.clone().offset(0.5);
+        let shell = sphere.clone().shell(0.5);
+        let onion = sphere.onion(0.2, 2);
+
+        let at = |r: f64| Vector3::new(0.6, 0.0, 0.8) * r;
+        let radial = at(1.0);
+        for (expr, r, dist) in [
+            (&offset, 2.0, -0.5),
+            (&offset, 3.0, 0.5),
+            (&shell, 1.875, -0.125),
+            (&shell, 1.0, 0.5),
+            (&shell, 2.5, 0.5),
+            // Two walls 0.1 thick, centered 0.1 inside and outside the sphere.
+            (&onion, 2.0, 0.05),
+            (&onion, 1.9, -0.05),
+            (&onion, 2.1, -0.05),
+            (&onion, 2.5, 0.35),
+        ] {
+            assert!(
+                (expr.eval(&at(r)) - dist).abs() < 1e-12,
+                "{} at {}",
+                dist,
+                r
+            );
+        }
+
+        // Inside the hollow the distance grows towards the center.
+        assert!((shell.grad(&at(1.0)) + radial).norm() < 1e-12);
+        assert!((shell.grad(&at(2.5)) - radial).norm() < 1e-12);
+        assert!((onion.grad(&at(1.95)) - radial).norm() < 1e-12);
+
+        // The field is positive between the walls, so both are separate surfaces.
+        for r in [1.96, 2.0, 2.04] {
+            assert!(onion.eval(&at(r)) > 0.0, "{}", r);
+        }
+    }
+}
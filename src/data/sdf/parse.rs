@@ -0,0 +1,693 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+use crate::Dimension;
+
+use super::{
+    node::{BinaryOp, NodeId, SDFNode, UnaryOp},
+    SDFExpression, SDFGraph,
+};
+
+// Expressions are written with the usual arithmetic operators, x, y and z, number literals and
+// function calls, for example min(sqrt(x*x + y*y + z*z) - 3, z - 1).
+// They may start with let bindings naming subexpressions, like let r = sqrt(x*x + y*y); r - 1.
+// Every expression prints in this syntax and parses back to the same expression.
+
+// A ParseError is the position and description of the first error in an expression's text.
+// Lines and columns start at 1.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl Error for ParseError {}
+
+#[derive(Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Symbol(char),
+    End,
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Number(n) => write!(f, "'{}'", n),
+            Token::Ident(name) => write!(f, "'{}'", name),
+            Token::Symbol(c) => write!(f, "'{}'", c),
+            Token::End => write!(f, "end of input"),
+        }
+    }
+}
+
+// A token and the line and column it starts at.
+struct Spanned {
+    token: Token,
+    line: usize,
+    column: usize,
+}
+
+fn tokenize(text: &str) -> Result<Vec<Spanned>, ParseError> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let (mut line, mut column) = (1, 1);
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+
+        let token = if c == '\n' {
+            line += 1;
+            column = 1;
+            i += 1;
+            continue;
+        } else if c.is_whitespace() {
+            None
+        } else if c.is_ascii_digit() || c == '.' {
+            while i + 1 < chars.len() && (chars[i + 1].is_ascii_digit() || chars[i + 1] == '.') {
+                i += 1;
+            }
+            // An exponent, like the e-3 in 1.5e-3.
+            if i + 1 < chars.len() && matches!(chars[i + 1], 'e' | 'E') {
+                let sign = matches!(chars.get(i + 2), Some('+' | '-')) as usize;
+                if chars.get(i + 2 + sign).is_some_and(|c| c.is_ascii_digit()) {
+                    i += 2 + sign;
+                    while i + 1 < chars.len() && chars[i + 1].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+
+            let literal: String = chars[start..=i].iter().collect();
+            match literal.parse() {
+                Ok(n) => Some(Token::Number(n)),
+                Err(_) => {
+                    return Err(ParseError {
+                        line,
+                        column,
+                        message: format!("invalid number '{}'", literal),
+                    })
+                }
+            }
+        } else if c.is_alphabetic() || c == '_' {
+            while i + 1 < chars.len() && (chars[i + 1].is_alphanumeric() || chars[i + 1] == '_') {
+                i += 1;
+            }
+
+            let name: String = chars[start..=i].iter().collect();
+            match name.as_str() {
+                "inf" => Some(Token::Number(f64::INFINITY)),
+                "NaN" => Some(Token::Number(f64::NAN)),
+                _ => Some(Token::Ident(name)),
+            }
+        } else if "+-*/^(),=;".contains(c) {
+            Some(Token::Symbol(c))
+        } else {
+            return Err(ParseError {
+                line,
+                column,
+                message: format!("unexpected character '{}'", c),
+            });
+        };
+
+        if let Some(token) = token {
+            tokens.push(Spanned {
+                token,
+                line,
+                column,
+            });
+        }
+        column += i + 1 - start;
+        i += 1;
+    }
+
+    tokens.push(Spanned {
+        token: Token::End,
+        line,
+        column,
+    });
+    Ok(tokens)
+}
+
+// A recursive descent parser, each method parses one level of precedence.
+struct Parser {
+    tokens: Vec<Spanned>,
+    pos: usize,
+    bindings: HashMap<String, SDFExpression>,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos].token
+    }
+
+    fn peek_second(&self) -> &Token {
+        &self.tokens[(self.pos + 1).min(self.tokens.len() - 1)].token
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.peek().clone();
+        self.pos = (self.pos + 1).min(self.tokens.len() - 1);
+        token
+    }
+
+    // An error at the start of the next token.
+    fn error(&self, message: String) -> ParseError {
+        let Spanned { line, column, .. } = self.tokens[self.pos];
+        ParseError {
+            line,
+            column,
+            message,
+        }
+    }
+
+    fn expect(&mut self, symbol: char) -> Result<(), ParseError> {
+        match self.peek() {
+            Token::Symbol(c) if *c == symbol => {
+                self.next();
+                Ok(())
+            }
+            token => Err(self.error(format!("expected '{}' but found {}", symbol, token))),
+        }
+    }
+
+    // Parses the let bindings before an expression.
+    fn bindings(&mut self) -> Result<SDFExpression, ParseError> {
+        while *self.peek() == Token::Ident("let".into()) {
+            self.next();
+
+            let start = self.pos;
+            let name = match self.next() {
+                Token::Ident(name)
+                    if !["x", "y", "z", "let"].contains(&name.as_str())
+                        && !self.bindings.contains_key(&name) =>
+                {
+                    name
+                }
+                token => {
+                    self.pos = start;
+                    return Err(self.error(format!("expected a new name but found {}", token)));
+                }
+            };
+
+            self.expect('=')?;
+            let value = self.sum()?;
+            self.expect(';')?;
+            self.bindings.insert(name, value);
+        }
+
+        self.sum()
+    }
+
+    fn sum(&mut self) -> Result<SDFExpression, ParseError> {
+        let mut expr = self.product()?;
+        loop {
+            match self.peek() {
+                Token::Symbol('+') => {
+                    self.next();
+                    expr = expr + self.product()?;
+                }
+                Token::Symbol('-') => {
+                    self.next();
+                    expr = expr - self.product()?;
+                }
+                _ => return Ok(expr),
+            }
+        }
+    }
+
+    fn product(&mut self) -> Result<SDFExpression, ParseError> {
+        let mut expr = self.prefix()?;
+        loop {
+            match self.peek() {
+                Token::Symbol('*') => {
+                    self.next();
+                    expr = expr * self.prefix()?;
+                }
+                Token::Symbol('/') => {
+                    self.next();
+                    expr = expr / self.prefix()?;
+                }
+                _ => return Ok(expr),
+            }
+        }
+    }
+
+    fn prefix(&mut self) -> Result<SDFExpression, ParseError> {
+        if *self.peek() != Token::Symbol('-') {
+            return self.power();
+        }
+        self.next();
+
+        // A minus sign directly before a number is part of the number,
+        // unless the number is raised to a power.
+        match (self.peek(), self.peek_second()) {
+            (Token::Number(_), Token::Symbol('^')) => Ok(-self.prefix()?),
+            (Token::Number(n), _) => {
+                let n = *n;
+                self.next();
+                Ok((-n).into())
+            }
+            _ => Ok(-self.prefix()?),
+        }
+    }
+
+    fn power(&mut self) -> Result<SDFExpression, ParseError> {
+        let base = self.atom()?;
+        if *self.peek() != Token::Symbol('^') {
+            return Ok(base);
+        }
+
+        self.next();
+        Ok(base.pow(self.prefix()?))
+    }
+
+    fn atom(&mut self) -> Result<SDFExpression, ParseError> {
+        let start = self.pos;
+        match self.next() {
+            Token::Number(n) => Ok(n.into()),
+            Token::Symbol('(') => {
+                let expr = self.sum()?;
+                self.expect(')')?;
+                Ok(expr)
+            }
+            Token::Ident(name) if *self.peek() == Token::Symbol('(') => {
+                self.next();
+                let mut args = Vec::new();
+                if *self.peek() != Token::Symbol(')') {
+                    args.push(self.sum()?);
+                    while *self.peek() == Token::Symbol(',') {
+                        self.next();
+                        args.push(self.sum()?);
+                    }
+                }
+                self.expect(')')?;
+
+                call(&name, args).map_err(|message| {
+                    self.pos = start;
+                    self.error(message)
+                })
+            }
+            Token::Ident(name) if self.bindings.contains_key(&name) => {
+                Ok(self.bindings[&name].clone())
+            }
+            Token::Ident(name) => match name.as_str() {
+                "x" => Ok(SDFExpression::x()),
+                "y" => Ok(SDFExpression::y()),
+                "z" => Ok(SDFExpression::z()),
+                _ => {
+                    self.pos = start;
+                    Err(self.error(format!("unknown variable '{}'", name)))
+                }
+            },
+            token => {
+                self.pos = start;
+                Err(self.error(format!("expected an expression but found {}", token)))
+            }
+        }
+    }
+}
+
+// Applies the function called name to args.
+fn call(name: &str, args: Vec<SDFExpression>) -> Result<SDFExpression, String> {
+    if name == "noise" {
+        return noise(args);
+    }
+
+    let arity = match name {
+        "sqrt" | "abs" | "recip" | "exp" | "ln" | "sin" | "cos" | "floor" => 1,
+        "pow" | "atan2" | "min" | "max" => 2,
+        "select" => 4,
+        _ => return Err(format!("unknown function '{}'", name)),
+    };
+    if args.len() != arity {
+        return Err(format!(
+            "{} takes {} argument{} but was given {}",
+            name,
+            arity,
+            if arity == 1 { "" } else { "s" },
+            args.len()
+        ));
+    }
+
+    let mut args = args.into_iter();
+    let mut arg = || args.next().unwrap();
+    Ok(match name {
+        "sqrt" => arg().sqrt(),
+        "abs" => arg().abs(),
+        "recip" => arg().recip(),
+        "exp" => arg().exp(),
+        "ln" => arg().ln(),
+        "sin" => arg().sin(),
+        "cos" => arg().cos(),
+        "floor" => arg().floor(),
+        "pow" => arg().pow(arg()),
+        "atan2" => arg().atan2(arg()),
+        "min" => SDFExpression::min(arg(), arg()),
+        "max" => SDFExpression::max(arg(), arg()),
+        "select" => SDFExpression::select(arg(), arg(), arg(), arg()),
+        _ => unreachable!(),
+    })
+}
+
+// Noise takes a point and a seed, optionally followed by the order of derivative along each axis.
+fn noise(args: Vec<SDFExpression>) -> Result<SDFExpression, String> {
+    if args.len() != 4 && args.len() != 7 {
+        return Err(format!(
+            "noise takes 4 or 7 arguments but was given {}",
+            args.len()
+        ));
+    }
+
+    let integer = |expr: &SDFExpression, max: f64| match expr.graph.node(expr.root) {
+        SDFNode::Const(c) if c.fract() == 0.0 && (0.0..=max).contains(c) => Ok(*c),
+        _ => Err("noise seeds and orders must be non-negative integers".to_string()),
+    };
+    let seed = integer(&args[3], u32::MAX as f64)? as u32;
+    let mut order = [0; 3];
+    for (i, arg) in args[4..].iter().enumerate() {
+        order[i] = integer(arg, u8::MAX as f64)? as u8;
+    }
+
+    let point = [args[0].clone(), args[1].clone(), args[2].clone()];
+    Ok(SDFExpression::combine(point, |graph, point| {
+        graph.push(SDFNode::Noise { seed, order, point })
+    }))
+}
+
+impl FromStr for SDFExpression {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?,
+            pos: 0,
+            bindings: HashMap::new(),
+        };
+
+        let expr = parser.bindings()?;
+        match parser.peek() {
+            Token::End => Ok(expr),
+            token => Err(parser.error(format!("unexpected {}", token))),
+        }
+    }
+}
+
+// How tightly each kind of node binds when printed, operands binding less tightly than their
+// position requires are wrapped in parentheses.
+const SUM: u8 = 1;
+const PRODUCT: u8 = 2;
+const PREFIX: u8 = 3;
+const ATOM: u8 = 4;
+
+fn precedence(node: &SDFNode) -> u8 {
+    match node {
+        SDFNode::Const(c) if c.is_sign_negative() => PREFIX,
+        SDFNode::Unary(UnaryOp::Neg, _) => PREFIX,
+        SDFNode::Binary(BinaryOp::Add, _, _) => SUM,
+        SDFNode::Binary(BinaryOp::Mul, _, _) => PRODUCT,
+        _ => ATOM,
+    }
+}
+
+// Shared nodes printing to more than this many nodes are bound to a name instead of being
+// written out wherever they're used, which could take time exponential in the graph's depth.
+const MAX_INLINE_SIZE: usize = 8;
+
+// The operands of a node in the order they're printed.
+fn printed_operands(node: &SDFNode) -> Vec<NodeId> {
+    match node {
+        // Min and max only print each operand once.
+        SDFNode::GT {
+            left,
+            right,
+            true_val,
+            false_val,
+        } if (true_val, false_val) == (left, right) || (true_val, false_val) == (right, left) => {
+            vec![*left, *right]
+        }
+        _ => node.operands(),
+    }
+}
+
+// Chooses the nodes to bind to names, giving each its position in the bindings.
+fn bound_nodes(graph: &SDFGraph, root: NodeId) -> BTreeMap<NodeId, usize> {
+    let reachable = graph.reachable(root);
+    let mut uses = vec![0; root.0 + 1];
+    for i in (0..=root.0).filter(|i| reachable[*i]) {
+        for operand in printed_operands(graph.node(NodeId(i))) {
+            uses[operand.0] += 1;
+        }
+    }
+
+    // Operands come before the nodes using them, so their sizes are known first.
+    let mut names = BTreeMap::new();
+    let mut sizes = vec![1; root.0 + 1];
+    for i in (0..=root.0).filter(|i| reachable[*i]) {
+        sizes[i] += printed_operands(graph.node(NodeId(i)))
+            .iter()
+            .map(|op| {
+                if names.contains_key(op) {
+                    1
+                } else {
+                    sizes[op.0]
+                }
+            })
+            .sum::<usize>();
+
+        if uses[i] > 1 && sizes[i] > MAX_INLINE_SIZE {
+            names.insert(NodeId(i), names.len());
+        }
+    }
+
+    names
+}
+
+// Writes a node, wrapping it in parentheses if it binds less tightly than min_precedence.
+// Nodes with names are written as their name.
+fn write_node(
+    graph: &SDFGraph,
+    names: &BTreeMap<NodeId, usize>,
+    id: NodeId,
+    min_precedence: u8,
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    if let Some(name) = names.get(&id) {
+        return write!(f, "t{}", name);
+    }
+
+    let node = graph.node(id);
+    if precedence(node) < min_precedence {
+        write!(f, "(")?;
+        write_node(graph, names, id, 0, f)?;
+        return write!(f, ")");
+    }
+
+    let call = |f: &mut fmt::Formatter<'_>, name: &str, args: &[NodeId]| {
+        write!(f, "{}(", name)?;
+        for (i, arg) in args.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write_node(graph, names, *arg, 0, f)?;
+        }
+        write!(f, ")")
+    };
+
+    match node {
+        SDFNode::Const(c) => write!(f, "{}", c),
+        SDFNode::Dim(d) => match d {
+            Dimension::X => write!(f, "x"),
+            Dimension::Y => write!(f, "y"),
+            Dimension::Z => write!(f, "z"),
+        },
+        SDFNode::Unary(UnaryOp::Neg, a) => {
+            write!(f, "-")?;
+            // Constants are wrapped so the minus sign isn't read as part of the number.
+            let min = match graph.node(*a) {
+                SDFNode::Const(_) => ATOM + 1,
+                _ => PREFIX,
+            };
+            write_node(graph, names, *a, min, f)
+        }
+        SDFNode::Unary(op, a) => {
+            let name = match op {
+                UnaryOp::Neg => unreachable!(),
+                UnaryOp::Sqrt => "sqrt",
+                UnaryOp::Abs => "abs",
+                UnaryOp::Recip => "recip",
+                UnaryOp::Exp => "exp",
+                UnaryOp::Ln => "ln",
+                UnaryOp::Sin => "sin",
+                UnaryOp::Cos => "cos",
+                UnaryOp::Floor => "floor",
+            };
+            call(f, name, &[*a])
+        }
+        SDFNode::Binary(BinaryOp::Add, a, b) => {
+            write_node(graph, names, *a, SUM, f)?;
+            match graph.node(*b) {
+                SDFNode::Unary(UnaryOp::Neg, c) if !names.contains_key(b) => {
+                    write!(f, " - ")?;
+                    write_node(graph, names, *c, PRODUCT, f)
+                }
+                _ => {
+                    write!(f, " + ")?;
+                    write_node(graph, names, *b, PRODUCT, f)
+                }
+            }
+        }
+        SDFNode::Binary(BinaryOp::Mul, a, b) => {
+            write_node(graph, names, *a, PRODUCT, f)?;
+            match graph.node(*b) {
+                SDFNode::Unary(UnaryOp::Recip, c) if !names.contains_key(b) => {
+                    write!(f, " / ")?;
+                    write_node(graph, names, *c, PREFIX, f)
+                }
+                _ => {
+                    write!(f, " * ")?;
+                    write_node(graph, names, *b, PREFIX, f)
+                }
+            }
+        }
+        SDFNode::Binary(BinaryOp::Pow, a, b) => call(f, "pow", &[*a, *b]),
+        SDFNode::Binary(BinaryOp::Atan2, a, b) => call(f, "atan2", &[*a, *b]),
+        SDFNode::GT {
+            left,
+            right,
+            true_val,
+            false_val,
+        } => {
+            if true_val == left && false_val == right {
+                call(f, "max", &[*left, *right])
+            } else if true_val == right && false_val == left {
+                call(f, "min", &[*left, *right])
+            } else {
+                call(f, "select", &[*left, *right, *true_val, *false_val])
+            }
+        }
+        SDFNode::Noise { seed, order, point } => {
+            write!(f, "noise(")?;
+            for p in point {
+                write_node(graph, names, *p, 0, f)?;
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", seed)?;
+            if *order != [0; 3] {
+                write!(f, ", {}, {}, {}", order[0], order[1], order[2])?;
+            }
+            write!(f, ")")
+        }
+    }
+}
+
+impl Display for SDFExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Each binding is written before its name is used, so it's written out in full.
+        let bound = bound_nodes(&self.graph, self.root);
+        let mut names = BTreeMap::new();
+        for (id, name) in bound {
+            write!(f, "let t{} = ", name)?;
+            write_node(&self.graph, &names, id, 0, f)?;
+            writeln!(f, ";")?;
+            names.insert(id, name);
+        }
+
+        write_node(&self.graph, &names, self.root, 0, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{UnitQuaternion, Vector3};
+
+    use crate::{SDFExpression, VolumetricFunc};
+
+    use super::ParseError;
+
+    #[test]
+    fn printed_expressions_parse_to_the_same_expression() {
+        let parsed: SDFExpression = "min(sqrt(x*x+y*y+z*z)-3, z-1)".parse().unwrap();
+        let built = SDFExpression::min(
+            SDFExpression::sphere(Vector3::zeros(), 3.0),
+            SDFExpression::z() - 1.0.into(),
+        );
+
+        let shape = SDFExpression::smooth_min(
+            SDFExpression::torus(Vector3::new(0.5, 0.0, 0.0), 1.5, 0.25)
+                .rotate(UnitQuaternion::from_euler_angles(0.3, -0.2, 0.1)),
+            SDFExpression::cone(Vector3::zeros(), 1.0, 2.0) / (-SDFExpression::y()).exp(),
+            0.3,
+        ) - SDFExpression::x()
+            .atan2(SDFExpression::y())
+            .pow((-2.5).into())
+            + -SDFExpression::from(2.0);
+        let shape = shape.displace(0.1, 2.0, 5);
+
+        for expr in [parsed.clone(), shape] {
+            let text = expr.to_string();
+            let reparsed: SDFExpression = text.parse().unwrap();
+            assert_eq!(reparsed.to_string(), text);
+
+            let at = Vector3::new(0.8, 0.6, -0.3);
+            assert_eq!(reparsed.eval(&at).to_bits(), expr.eval(&at).to_bits());
+        }
+
+        let at = Vector3::new(0.1, 2.0, 0.4);
+        assert_eq!(parsed.eval(&at), built.eval(&at));
+        assert_eq!("-2^2".parse::<SDFExpression>().unwrap().eval(&at), -4.0);
+    }
+
+    #[test]
+    fn errors_report_their_position() {
+        let error = |text: &str| text.parse::<SDFExpression>().err().unwrap();
+        let at = |e: ParseError| (e.line, e.column);
+
+        assert_eq!(at(error("min(x,\n   y + )")), (2, 8));
+        assert_eq!(at(error("x * w")), (1, 5));
+        assert_eq!(at(error("sqrt(x, y)")), (1, 1));
+        assert_eq!(at(error("(x + 1")), (1, 7));
+        assert_eq!(at(error("x $ 1")), (1, 3));
+        assert_eq!(at(error("1 + noise(x, y, z, 0.5)")), (1, 5));
+    }
+
+    #[test]
+    fn shared_nodes_are_bound_to_names() {
+        // Written out in full this would take 2^40 copies of x + 1.
+        let mut expr = SDFExpression::sqrt(SDFExpression::x() * SDFExpression::y() + 1.0.into());
+        for _ in 0..40 {
+            expr = SDFExpression::smooth_min(expr.clone(), expr.clone() * 2.0.into(), 0.5);
+        }
+
+        let text = expr.to_string();
+        assert!(text.len() < 10_000, "{}", text.len());
+        let reparsed: SDFExpression = text.parse().unwrap();
+        assert_eq!(reparsed.to_string(), text);
+        let at = Vector3::new(0.3, -0.2, 0.1);
+        assert_eq!(reparsed.eval(&at).to_bits(), expr.eval(&at).to_bits());
+
+        let parsed: SDFExpression = "let r = sqrt(x*x + y*y);\nlet s = r * r;\ns - r"
+            .parse()
+            .unwrap();
+        assert_eq!(parsed.eval(&Vector3::new(3.0, 4.0, 0.0)), 20.0);
+
+        let error = |text: &str| text.parse::<SDFExpression>().err().unwrap();
+        assert_eq!(error("let a = 1; let a = 2; a").column, 16);
+        assert_eq!(error("let x = 1; x").column, 5);
+        assert_eq!(error("let a = 1 a").column, 11);
+    }
+}
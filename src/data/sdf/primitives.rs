@@ -0,0 +1,260 @@
+use nalgebra::Vector3;
+
+use super::SDFExpression;
+
+// Constructors for common shapes.
+// Each primitive is negative inside the shape, positive outside and zero on its surface.
+// Apart from the ellipsoid, values are the exact euclidean distance to the surface.
+impl SDFExpression {
+    // A sphere of the given radius.
+    pub fn sphere(center: Vector3<f64>, radius: f64) -> Self {
+        Self::length(Self::centered_axes(&center)) - radius.into()
+    }
+
+    // An axis aligned box extending half_size from center in each dimension.
+    pub fn cuboid(center: Vector3<f64>, half_size: Vector3<f64>) -> Self {
+        let [x, y, z] = Self::centered_axes(&center);
+        let q = [
+            x.abs() - half_size.x.into(),
+            y.abs() - half_size.y.into(),
+            z.abs() - half_size.z.into(),
+        ];
+
+        Self::box_distance(q)
+    }
+
+    // An axis aligned box extending half_size from center in each dimension,
+    // with edges and corners rounded off by radius.
+    pub fn rounded_cuboid(center: Vector3<f64>, half_size: Vector3<f64>, radius: f64) -> Self {
+        Self::cuboid(center, half_size.add_scalar(-radius)) - radius.into()
+    }
+
+    // A torus around an axis parallel to Z.
+    // major_radius is the distance from center to the middle of the tube, minor_radius is the tube's radius.
+    pub fn torus(center: Vector3<f64>, major_radius: f64, minor_radius: f64) -> Self {
+        let [x, y, z] = Self::centered_axes(&center);
+        let ring = Self::length([x, y]) - major_radius.into();
+
+        Self::length([ring, z]) - minor_radius.into()
+    }
+
+    // A capsule made of all points within radius of the line segment from a to b.
+    // When a and b are the same point this is a sphere.
+    pub fn capsule(a: Vector3<f64>, b: Vector3<f64>, radius: f64) -> Self {
+        let pa = Self::centered_axes(&a);
+        let ba = b - a;
+        let ba_len_sq = ba.norm_squared();
+        if ba_len_sq == 0.0 {
+            return Self::sphere(a, radius);
+        }
+
+        let proj = Self::dot(pa.clone(), [ba.x.into(), ba.y.into(), ba.z.into()])
+            * (1.0 / ba_len_sq).into();
+        let t = Self::clamp(proj, 0.0, 1.0);
+
+        let d = [0, 1, 2].map(|i| pa[i].clone() - t.clone() * ba[i].into());
+        Self::length(d) - radius.into()
+    }
+
+    // A capped cylinder along an axis parallel to Z, extending half_height above and below center.
+    pub fn cylinder(center: Vector3<f64>, radius: f64, half_height: f64) -> Self {
+        let [x, y, z] = Self::centered_axes(&center);
+        let q = [
+            Self::length([x, y]) - radius.into(),
+            z.abs() - half_height.into(),
+        ];
+
+        Self::box_distance(q)
+    }
+
+    // A capped cone along an axis parallel to Z.
+    // The base of the cone is centered on base and its tip is height above it.
+    pub fn cone(base: Vector3<f64>, radius: f64, height: f64) -> Self {
+        let [x, y, z] = Self::centered_axes(&base);
+        let half_height = 0.5 * height;
+
+        // Work in the 2D half plane through the axis, with the origin half way up the cone.
+        let qx = Self::length([x, y]);
+        let qy = z - half_height.into();
+
+        // Offset to the nearest point on the base or tip.
+        let cap_radius = Self::select(qy.clone(), 0.0.into(), 0.0.into(), radius.into());
+        let ca = [
+            qx.clone() - Self::min(qx.clone(), cap_radius),
+            qy.clone().abs() - half_height.into(),
+        ];
+
+        // Offset to the nearest point on the slanted side.
+        let (k1, k2) = ([0.0, half_height], [-radius, height]);
+        let t = Self::dot(
+            [
+                Self::from(k1[0]) - qx.clone(),
+                Self::from(k1[1]) - qy.clone(),
+            ],
+            [k2[0].into(), k2[1].into()],
+        ) * (1.0 / (k2[0] * k2[0] + k2[1] * k2[1])).into();
+        let t = Self::clamp(t, 0.0, 1.0);
+        let cb = [
+            qx + (-k1[0]).into() + t.clone() * k2[0].into(),
+            qy + (-k1[1]).into() + t * k2[1].into(),
+        ];
+
+        // Inside when below the side and between the caps.
+        let sign = Self::select(
+            cb[0].clone(),
+            0.0.into(),
+            1.0.into(),
+            Self::select(ca[1].clone(), 0.0.into(), 1.0.into(), (-1.0).into()),
+        );
+        let dist_sq = Self::min(Self::dot(ca.clone(), ca), Self::dot(cb.clone(), cb));
+
+        sign * dist_sq.sqrt()
+    }
+
+    // A half space bounded by a plane with the given normal passing through point.
+    // The side normal points toward is outside.
+    pub fn plane(normal: Vector3<f64>, point: Vector3<f64>) -> Self {
+        let n = normal.normalize();
+        let [x, y, z] = Self::centered_axes(&point);
+
+        Self::dot([x, y, z], [n.x.into(), n.y.into(), n.z.into()])
+    }
+
+    // An axis aligned ellipsoid with the given radius in each dimension.
+    // Ellipsoids have no closed form distance so this is a bound that is exact on the surface.
+    pub fn ellipsoid(center: Vector3<f64>, radii: Vector3<f64>) -> Self {
+        let [x, y, z] = Self::centered_axes(&center);
+        let k0 = Self::length([
+            x.clone() * (1.0 / radii.x).into(),
+            y.clone() * (1.0 / radii.y).into(),
+            z.clone() * (1.0 / radii.z).into(),
+        ]);
+        let k1 = Self::length([
+            x * (1.0 / (radii.x * radii.x)).into(),
+            y * (1.0 / (radii.y * radii.y)).into(),
+            z * (1.0 / (radii.z * radii.z)).into(),
+        ]);
+
+        // At the center both lengths are 0, where the distance is to the nearest point on the
+        // shortest axis.
+        Self::select(
+            k1.clone(),
+            0.0.into(),
+            k0.clone() * (k0 - 1.0.into()) / k1,
+            (-radii.min()).into(),
+        )
+    }
+
+    // Returns x, y and z offset by -center.
+    pub(super) fn centered_axes(center: &Vector3<f64>) -> [Self; 3] {
+        [
+            Self::x() - center.x.into(),
+            Self::y() - center.y.into(),
+            Self::z() - center.z.into(),
+        ]
+    }
+
+    // The distance to an axis aligned box given the offsets from each of its faces.
+    pub(super) fn box_distance<const N: usize>(q: [Self; N]) -> Self {
+        let outside = Self::length(q.clone().map(|q| Self::max(q, 0.0.into())));
+        let max_q = q.into_iter().reduce(Self::max).unwrap_or_default();
+
+        outside + Self::min(max_q, 0.0.into())
+    }
+
+    pub(super) fn clamp(a: Self, min: f64, max: f64) -> Self {
+        Self::min(Self::max(a, min.into()), max.into())
+    }
+
+    // The length of a vector, with a gradient of 0 instead of NaN where the length is 0.
+    pub(super) fn length<const N: usize>(a: [Self; N]) -> Self {
+        let len_sq = Self::dot(a.clone(), a);
+        Self::select(len_sq.clone(), 0.0.into(), len_sq.sqrt(), 0.0.into())
+    }
+
+    pub(super) fn dot<const N: usize>(a: [Self; N], b: [Self; N]) -> Self {
+        a.into_iter()
+            .zip(b)
+            .map(|(a, b)| a * b)
+            .reduce(|acc, ab| acc + ab)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+
+    use crate::{SDFExpression, VolumetricFunc};
+
+    fn shapes() -> Vec<SDFExpression> {
+        let c = Vector3::new(1.0, -0.5, 0.25);
+        vec![
+            SDFExpression::sphere(c, 1.5),
+            SDFExpression::cuboid(c, Vector3::new(1.0, 2.0, 0.5)),
+            SDFExpression::rounded_cuboid(c, Vector3::new(1.0, 2.0, 0.5), 0.25),
+            SDFExpression::torus(c, 2.0, 0.5),
+            SDFExpression::capsule(c, c + Vector3::new(1.0, 1.0, 0.0), 0.5),
+            SDFExpression::cylinder(c, 1.0, 2.0),
+            SDFExpression::cone(c, 1.0, 2.0),
+            SDFExpression::plane(Vector3::new(0.0, 0.0, 1.0), c),
+            SDFExpression::ellipsoid(c, Vector3::new(1.0, 2.0, 3.0)),
+        ]
+    }
+
+    #[test]
+    fn primitive_signs() {
+        let c = Vector3::new(1.0, -0.5, 0.25);
+        let inside = [
+            c,
+            c,
+            c,
+            c + Vector3::new(2.0, 0.0, 0.0),
+            c + Vector3::new(0.5, 0.5, 0.0),
+            c,
+            c + Vector3::new(0.0, 0.0, 0.5),
+            c - Vector3::new(0.0, 0.0, 1.0),
+            c + Vector3::new(0.1, 0.2, 0.3),
+        ];
+
+        for (shape, inside) in shapes().iter().zip(inside) {
+            assert!(shape.eval(&inside) < 0.0);
+            assert!(shape.eval(&(c + Vector3::new(10.0, 10.0, 10.0))) > 0.0);
+        }
+
+        // Degenerate positions and shapes still give distances.
+        let ellipsoid = SDFExpression::ellipsoid(c, Vector3::new(1.0, 2.0, 3.0));
+        assert_eq!(ellipsoid.eval(&c), -1.0);
+        assert_eq!(ellipsoid.grad(&c), Vector3::zeros());
+        let capsule = SDFExpression::capsule(c, c, 0.5);
+        assert_eq!(capsule.eval(&(c + Vector3::new(0.0, 2.0, 0.0))), 1.5);
+    }
+
+    #[test]
+    fn primitive_gradients() {
+        // One sample away from the shapes and one close to their centers.
+        let c = Vector3::new(1.0, -0.5, 0.25);
+        let h = 1e-6;
+
+        for at in [
+            Vector3::new(1.3, 0.7, -0.4),
+            c + Vector3::new(0.05, 0.03, 0.02),
+        ] {
+            for shape in shapes() {
+                let grad = shape.grad(&at);
+                for i in 0..3 {
+                    let mut offset = Vector3::zeros();
+                    offset[i] = h;
+                    let fd = (shape.eval(&(at + offset)) - shape.eval(&(at - offset))) / (2.0 * h);
+                    assert!(
+                        (grad[i] - fd).abs() < 1e-4,
+                        "{} != {} at {}",
+                        grad[i],
+                        fd,
+                        at
+                    );
+                }
+            }
+        }
+    }
+}
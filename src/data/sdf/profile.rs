@@ -0,0 +1,226 @@
+use nalgebra::Vector2;
+
+use crate::Dimension;
+
+use super::SDFExpression;
+
+// An SDFProfile is a 2D signed distance function, such as a sketch of a part's cross section.
+// Profiles are expressions of x and y, which are lifted into 3D by extrude or revolve.
+#[derive(Clone)]
+pub struct SDFProfile {
+    expr: SDFExpression,
+}
+
+impl SDFProfile {
+    // A circle of the given radius.
+    pub fn circle(center: Vector2<f64>, radius: f64) -> Self {
+        (SDFExpression::length(Self::centered_axes(&center)) - radius.into()).into()
+    }
+
+    // An axis aligned rectangle extending half_size from center in each dimension.
+    pub fn rectangle(center: Vector2<f64>, half_size: Vector2<f64>) -> Self {
+        let [x, y] = Self::centered_axes(&center);
+        let q = [x.abs() - half_size.x.into(), y.abs() - half_size.y.into()];
+
+        SDFExpression::box_distance(q).into()
+    }
+
+    // The distance to the line segment from a to b.
+    // This is never negative, subtract a thickness to give the segment an inside.
+    // When a and b are the same point this is the distance to that point.
+    pub fn segment(a: Vector2<f64>, b: Vector2<f64>) -> Self {
+        let pa = Self::centered_axes(&a);
+        let ba = b - a;
+        if ba.norm_squared() == 0.0 {
+            return Self::circle(a, 0.0);
+        }
+
+        let proj = SDFExpression::dot(pa.clone(), [ba.x.into(), ba.y.into()])
+            * (1.0 / ba.norm_squared()).into();
+        let t = SDFExpression::clamp(proj, 0.0, 1.0);
+
+        let d = [0, 1].map(|i| pa[i].clone() - t.clone() * ba[i].into());
+        SDFExpression::length(d).into()
+    }
+
+    // A closed polygon with the given vertices, of which there must be at least 3.
+    // The polygon may be concave but shouldn't intersect itself. Repeated vertices are ignored.
+    pub fn polygon(vertices: &[Vector2<f64>]) -> Self {
+        assert!(vertices.len() >= 3, "Polygons need at least 3 vertices.");
+
+        let y = SDFExpression::y();
+        let mut dist_sq: Option<SDFExpression> = None;
+        let mut sign = SDFExpression::from(1.0);
+
+        for (i, vi) in vertices.iter().enumerate() {
+            let vj = vertices[(i + vertices.len() - 1) % vertices.len()];
+            let e = vj - vi;
+            if e.norm_squared() == 0.0 {
+                continue;
+            }
+            let w = Self::centered_axes(vi);
+
+            // The squared distance to the edge from vi to vj.
+            let proj = SDFExpression::dot(w.clone(), [e.x.into(), e.y.into()])
+                * (1.0 / e.norm_squared()).into();
+            let t = SDFExpression::clamp(proj, 0.0, 1.0);
+            let b = [0, 1].map(|k| w[k].clone() - t.clone() * e[k].into());
+            let edge_sq = SDFExpression::dot(b.clone(), b);
+            dist_sq = Some(match dist_sq {
+                Some(d) => SDFExpression::min(d, edge_sq),
+                None => edge_sq,
+            });
+
+            // Each edge crossed by a ray from the point along x flips the sign.
+            let above_start = step(vi.y.into(), y.clone(), 0.0, 1.0);
+            let below_end = step(vj.y.into(), y.clone(), 1.0, 0.0);
+            let left = step(
+                w[1].clone() * e.x.into(),
+                w[0].clone() * e.y.into(),
+                1.0,
+                0.0,
+            );
+            let count = above_start + below_end + left;
+            let crosses =
+                step(count.clone(), 2.5.into(), 1.0, 0.0) + step(0.5.into(), count, 1.0, 0.0);
+            sign = sign * (SDFExpression::from(1.0) - crosses * 2.0.into());
+        }
+
+        let dist_sq = dist_sq.unwrap_or_default();
+        let dist = SDFExpression::select(dist_sq.clone(), 0.0.into(), dist_sq.sqrt(), 0.0.into());
+        (sign * dist).into()
+    }
+
+    pub fn min(a: Self, b: Self) -> Self {
+        SDFExpression::min(a.expr, b.expr).into()
+    }
+
+    pub fn max(a: Self, b: Self) -> Self {
+        SDFExpression::max(a.expr, b.expr).into()
+    }
+
+    // Removes b from a.
+    pub fn subtract(a: Self, b: Self) -> Self {
+        SDFExpression::max(a.expr, -b.expr).into()
+    }
+
+    // Extrudes this profile along z, from z = 0 to z = height.
+    pub fn extrude(self, height: f64) -> SDFExpression {
+        let half_height = 0.5 * height;
+        let z = SDFExpression::z();
+        let q = [
+            self.expr,
+            (z - half_height.into()).abs() - half_height.into(),
+        ];
+
+        SDFExpression::box_distance(q)
+    }
+
+    // Revolves this profile about an axis through the origin.
+    // The profile's x is the distance from the axis and its y is the position along the axis,
+    // so only the part of the profile where x >= 0 is used.
+    pub fn revolve(self, axis: Dimension) -> SDFExpression {
+        let others = match axis {
+            Dimension::X => [Dimension::Y, Dimension::Z],
+            Dimension::Y => [Dimension::Z, Dimension::X],
+            Dimension::Z => [Dimension::X, Dimension::Y],
+        };
+        let radial = SDFExpression::length(others.map(SDFExpression::from));
+
+        self.expr
+            .substitute([radial, axis.into(), SDFExpression::z()])
+    }
+
+    // Returns x and y offset by -center.
+    fn centered_axes(center: &Vector2<f64>) -> [SDFExpression; 2] {
+        [
+            SDFExpression::x() - center.x.into(),
+            SDFExpression::y() - center.y.into(),
+        ]
+    }
+}
+
+// Returns true_val where left > right and false_val elsewhere.
+fn step(left: SDFExpression, right: SDFExpression, true_val: f64, false_val: f64) -> SDFExpression {
+    SDFExpression::select(left, right, true_val.into(), false_val.into())
+}
+
+// Any expression can be used as a profile, z is ignored when it's extruded or revolved.
+impl From<SDFExpression> for SDFProfile {
+    fn from(expr: SDFExpression) -> Self {
+        Self { expr }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{Vector2, Vector3};
+
+    use crate::{Dimension, SDFExpression, SDFProfile, VolumetricFunc};
+
+    #[test]
+    fn lifted_profiles_match_primitives() {
+        let extruded =
+            SDFProfile::rectangle(Vector2::new(0.5, 0.0), Vector2::new(1.0, 2.0)).extrude(3.0);
+        let cuboid =
+            SDFExpression::cuboid(Vector3::new(0.5, 0.0, 1.5), Vector3::new(1.0, 2.0, 1.5));
+
+        let revolved = SDFProfile::circle(Vector2::new(2.0, 0.0), 0.5).revolve(Dimension::Z);
+        let torus = SDFExpression::torus(Vector3::zeros(), 2.0, 0.5);
+
+        let square =
+            [(-0.5, -2.0), (1.5, -2.0), (1.5, 2.0), (-0.5, 2.0)].map(|(x, y)| Vector2::new(x, y));
+        let polygon = SDFProfile::polygon(&square).extrude(3.0);
+
+        for at in [
+            Vector3::new(0.1, 0.2, 0.3),
+            Vector3::new(2.0, -1.0, 0.5),
+            Vector3::new(-0.7, 2.9, -0.2),
+            Vector3::new(1.4, 0.3, 3.5),
+        ] {
+            assert!((extruded.eval(&at) - cuboid.eval(&at)).abs() < 1e-12);
+            assert!((polygon.eval(&at) - cuboid.eval(&at)).abs() < 1e-12);
+            assert!((revolved.eval(&at) - torus.eval(&at)).abs() < 1e-12);
+        }
+
+        // A concave L shape.
+        let l = [
+            (0.0, 0.0),
+            (2.0, 0.0),
+            (2.0, 1.0),
+            (1.0, 1.0),
+            (1.0, 2.0),
+            (0.0, 2.0),
+        ]
+        .map(|(x, y)| Vector2::new(x, y));
+        let l = SDFProfile::polygon(&l).extrude(1.0);
+        assert!((l.eval(&Vector3::new(1.5, 1.5, 0.5)) - 0.5).abs() < 1e-12);
+        assert!((l.eval(&Vector3::new(0.5, 1.5, 0.5)) + 0.5).abs() < 1e-12);
+
+        // Repeated vertices and points as segments don't create infinite constants.
+        let repeated = [
+            square[0], square[1], square[1], square[2], square[3], square[0],
+        ];
+        let repeated = SDFProfile::polygon(&repeated).extrude(3.0);
+        let point =
+            SDFProfile::segment(Vector2::new(1.0, 0.0), Vector2::new(1.0, 0.0)).extrude(0.0);
+        let sphere = SDFExpression::sphere(Vector3::new(1.0, 0.0, 0.0), 0.0);
+        for at in [Vector3::new(0.1, 0.2, 0.3), Vector3::new(2.0, -1.0, 0.0)] {
+            assert!((repeated.eval(&at) - cuboid.eval(&at)).abs() < 1e-12);
+            assert!((point.eval(&at) - sphere.eval(&at)).abs() < 1e-12);
+        }
+        assert!(!repeated.to_string().contains("inf"));
+        assert!(!point.to_string().contains("inf"));
+
+        let segment = SDFProfile::segment(Vector2::zeros(), Vector2::new(2.0, 0.0));
+        let capsule = SDFExpression::capsule(Vector3::zeros(), Vector3::new(2.0, 0.0, 0.0), 0.0);
+        let at = Vector3::new(2.5, 0.7, 0.0);
+        assert!((segment.extrude(0.0).eval(&at) - capsule.eval(&at)).abs() < 1e-12);
+    }
+
+    #[test]
+    #[should_panic]
+    fn polygons_need_three_vertices() {
+        SDFProfile::polygon(&[]);
+    }
+}
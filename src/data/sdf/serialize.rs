@@ -0,0 +1,121 @@
+use std::sync::Arc;
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{
+    node::{NodeId, SDFNode},
+    SDFExpression, SDFGraph,
+};
+
+// Expressions are serialized as the list of nodes the root depends on, in topological order.
+// Shared nodes are only stored once and the root is the last node.
+#[derive(Serialize, Deserialize)]
+struct SerializedExpression {
+    nodes: Vec<SDFNode>,
+}
+
+impl Serialize for SDFExpression {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // Importing into an empty graph drops nodes the root doesn't depend on.
+        let mut graph = SDFGraph::default();
+        graph.import(&self.graph, self.root);
+
+        SerializedExpression {
+            nodes: graph.nodes().to_vec(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SDFExpression {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let SerializedExpression { nodes } = SerializedExpression::deserialize(deserializer)?;
+        if nodes.is_empty() {
+            return Err(de::Error::custom("an expression needs at least one node"));
+        }
+
+        // Nodes are pushed rather than copied so the graph is still hash-consed,
+        // even if the input wasn't written by serialize.
+        let mut graph = SDFGraph::default();
+        let mut map = Vec::with_capacity(nodes.len());
+        for (i, node) in nodes.iter().enumerate() {
+            if let Some(operand) = node.operands().into_iter().find(|o| o.0 >= i) {
+                return Err(de::Error::custom(format!(
+                    "node {} refers to node {}, which doesn't come before it",
+                    i, operand.0
+                )));
+            }
+
+            let node = node.map_operands(|id: NodeId| map[id.0]);
+            map.push(graph.push(node));
+        }
+
+        let root = *map.last().unwrap();
+        Ok(Self::new(Arc::new(graph), root))
+    }
+}
+
+// Constants that aren't finite, like those parsed from "1e400", are written as strings since
+// formats like JSON have no numbers for them.
+pub(super) mod constant {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Constant {
+        Number(f64),
+        Text(String),
+    }
+
+    pub fn serialize<S: Serializer>(val: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+        if val.is_finite() {
+            serializer.serialize_f64(*val)
+        } else {
+            serializer.serialize_str(&val.to_string())
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+        match Constant::deserialize(deserializer)? {
+            Constant::Number(val) => Ok(val),
+            Constant::Text(text) => text
+                .parse()
+                .map_err(|_| de::Error::custom(format!("\"{}\" isn't a number", text))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+
+    use crate::{SDFExpression, VolumetricFunc};
+
+    #[test]
+    fn shared_nodes_are_serialized_once() {
+        let mut expr = SDFExpression::x() + 1.0.into();
+        for _ in 0..40 {
+            expr = expr.clone() * expr;
+        }
+        let expr = SDFExpression::min(expr, SDFExpression::sphere(Vector3::zeros(), 2.0));
+
+        let json = serde_json::to_string(&expr).unwrap();
+        let copy: SDFExpression = serde_json::from_str(&json).unwrap();
+
+        let at = Vector3::new(-0.5, 0.2, 0.1);
+        assert_eq!(copy.eval(&at), expr.eval(&at));
+        assert_eq!(copy.grad(&at), expr.grad(&at));
+        assert!(json.len() < 2000);
+
+        // Infinite constants survive the round trip.
+        let expr = SDFExpression::min(SDFExpression::x(), f64::INFINITY.into())
+            + SDFExpression::max(SDFExpression::y(), f64::NEG_INFINITY.into());
+        let json = serde_json::to_string(&expr).unwrap();
+        assert!(json.contains("\"-inf\""));
+        let copy: SDFExpression = serde_json::from_str(&json).unwrap();
+        assert_eq!(copy.eval(&at), expr.eval(&at));
+
+        let invalid = r#"{"nodes":[{"Unary":["Neg",0]}]}"#;
+        assert!(serde_json::from_str::<SDFExpression>(invalid).is_err());
+    }
+}
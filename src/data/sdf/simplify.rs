@@ -0,0 +1,210 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use super::{
+    graph::SDFGraph,
+    node::{BinaryOp, NodeId, SDFNode, UnaryOp},
+    SDFExpression,
+};
+
+// A sum of nodes multiplied by constant coefficients, plus a constant.
+// Terms are kept in node order so equal sums are built into equal nodes.
+#[derive(Clone, Default)]
+struct Sum {
+    constant: f64,
+    terms: BTreeMap<NodeId, f64>,
+}
+
+impl Sum {
+    fn merge(mut self, other: Sum) -> Sum {
+        self.constant += other.constant;
+        for (base, c) in other.terms {
+            *self.terms.entry(base).or_insert(0.0) += c;
+        }
+        self
+    }
+}
+
+impl SDFGraph {
+    // Copies the nodes roots depend on from other into this graph, simplifying them on the way.
+    // Constants are folded, like terms of sums are merged, terms and factors that don't change
+    // the result are dropped and selections between equal values are removed.
+    pub(super) fn simplify<const N: usize>(
+        &mut self,
+        other: &SDFGraph,
+        roots: [NodeId; N],
+    ) -> [NodeId; N] {
+        let mask = other.reachable_from(roots);
+        let mut map = vec![NodeId(0); mask.len()];
+        let mut sums = HashMap::new();
+        for i in (0..mask.len()).filter(|i| mask[*i]) {
+            let node = other.node(NodeId(i)).map_operands(|id| map[id.0]);
+            map[i] = self.push_simplified(node, &mut sums);
+        }
+
+        roots.map(|r| map[r.0])
+    }
+
+    // Pushes a simplified equivalent of node, whose operands are already simplified.
+    // sums remembers the terms of the sums built so far, so they can be merged into larger sums.
+    fn push_simplified(&mut self, node: SDFNode, sums: &mut HashMap<NodeId, Sum>) -> NodeId {
+        let operands = node.operands();
+        let constant = |id: NodeId| match self.node(id) {
+            SDFNode::Const(c) => Some(*c),
+            _ => None,
+        };
+        if !operands.is_empty() && operands.iter().all(|o| constant(*o).is_some()) {
+            let val = node.eval(&[0.0; 3], |id| constant(id).unwrap());
+            return self.constant(val);
+        }
+
+        match node {
+            SDFNode::Unary(UnaryOp::Neg, a) => {
+                let (c, base) = self.coefficient(a);
+                self.scaled(-c, base)
+            }
+            SDFNode::Unary(UnaryOp::Abs, a) => match self.node(a) {
+                SDFNode::Unary(UnaryOp::Abs, _) => a,
+                SDFNode::Unary(UnaryOp::Neg, b) => {
+                    let b = *b;
+                    self.unary(UnaryOp::Abs, b)
+                }
+                _ => self.push(node),
+            },
+            SDFNode::Binary(BinaryOp::Add, a, b) => {
+                let sum = self.sum_terms(a, sums).merge(self.sum_terms(b, sums));
+                self.push_sum(sum, sums)
+            }
+            SDFNode::Binary(BinaryOp::Mul, a, b) => {
+                let (ca, a) = self.coefficient(a);
+                let (cb, b) = self.coefficient(b);
+                let base = match (a, b) {
+                    (Some(a), Some(b)) => Some(self.mul(a.min(b), a.max(b))),
+                    (a, None) => a,
+                    (None, b) => b,
+                };
+                self.scaled(ca * cb, base)
+            }
+            SDFNode::Binary(BinaryOp::Pow, a, b) => match constant(b) {
+                Some(1.0) => a,
+                Some(0.0) => self.constant(1.0),
+                _ => self.push(node),
+            },
+            // Equal branches, or left > left which is never true, always give false_val.
+            SDFNode::GT {
+                left,
+                right,
+                true_val,
+                false_val,
+            } if true_val == false_val || left == right => false_val,
+            _ => self.push(node),
+        }
+    }
+
+    // Splits a simplified node into a constant coefficient and the node it multiplies.
+    // Constants have no node.
+    fn coefficient(&self, id: NodeId) -> (f64, Option<NodeId>) {
+        match self.node(id) {
+            SDFNode::Const(c) => (*c, None),
+            SDFNode::Unary(UnaryOp::Neg, a) => (-1.0, Some(*a)),
+            SDFNode::Binary(BinaryOp::Mul, a, b) => match self.node(*a) {
+                SDFNode::Const(c) => (*c, Some(*b)),
+                _ => (1.0, Some(id)),
+            },
+            _ => (1.0, Some(id)),
+        }
+    }
+
+    // Pushes c * base, or c if there's no base.
+    fn scaled(&mut self, c: f64, base: Option<NodeId>) -> NodeId {
+        match base {
+            None => self.constant(c),
+            Some(_) if c == 0.0 => self.constant(0.0),
+            Some(base) if c == 1.0 => base,
+            Some(base) if c == -1.0 => self.unary(UnaryOp::Neg, base),
+            Some(base) => {
+                let c = self.constant(c);
+                self.mul(c, base)
+            }
+        }
+    }
+
+    fn sum_terms(&self, id: NodeId, sums: &HashMap<NodeId, Sum>) -> Sum {
+        if let Some(sum) = sums.get(&id) {
+            return sum.clone();
+        }
+
+        let mut sum = Sum::default();
+        match self.coefficient(id) {
+            (c, None) => sum.constant = c,
+            (c, Some(base)) => {
+                sum.terms.insert(base, c);
+            }
+        }
+        sum
+    }
+
+    fn push_sum(&mut self, sum: Sum, sums: &mut HashMap<NodeId, Sum>) -> NodeId {
+        let mut acc = None;
+        for (base, c) in sum.terms.iter().filter(|(_, c)| **c != 0.0) {
+            let term = self.scaled(*c, Some(*base));
+            acc = Some(match acc {
+                Some(acc) => self.add(acc, term),
+                None => term,
+            });
+        }
+
+        let id = match acc {
+            Some(acc) if sum.constant == 0.0 => acc,
+            Some(acc) => {
+                let c = self.constant(sum.constant);
+                self.add(acc, c)
+            }
+            None => self.constant(sum.constant),
+        };
+        if let SDFNode::Binary(BinaryOp::Add, _, _) = self.node(id) {
+            sums.insert(id, sum);
+        }
+        id
+    }
+}
+
+impl SDFExpression {
+    // Returns an equivalent expression with constants folded and redundant operations removed.
+    // Values may differ in the last bits as constants are combined in a different order.
+    pub fn simplify(&self) -> Self {
+        let mut graph = SDFGraph::default();
+        let [root] = graph.simplify(&self.graph, [self.root]);
+        Self::new(Arc::new(graph), root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+
+    use crate::{Dimension, SDFExpression, VolumetricFunc};
+
+    #[test]
+    fn simplified_expressions_keep_their_values() {
+        let (x, y) = (SDFExpression::x(), SDFExpression::y());
+        let expr = (x.clone() * 1.0.into() + 0.0.into()) * (y.clone() - y.clone())
+            + x.clone()
+            + x.clone() * 3.0.into()
+            + SDFExpression::from(2.0) * 4.0.into()
+            + SDFExpression::select(y.clone(), x.clone(), 2.0.into(), 2.0.into());
+        assert_eq!(expr.simplify().to_string(), "4 * x + 10");
+
+        let expr = -(-(x.clone() * 2.0.into())) * (y.clone() * 0.5.into()) - (x * y).abs();
+        assert_eq!(expr.simplify().to_string(), "x * y - abs(x * y)");
+
+        let sphere = SDFExpression::sphere(Vector3::new(0.5, 0.0, -1.0), 2.0);
+        let twisted = sphere.clone().twist(Dimension::Z, 0.3);
+        let shape = SDFExpression::smooth_min(sphere, twisted, 0.4);
+        let at = Vector3::new(0.3, -1.2, 0.7);
+        assert!((shape.simplify().eval(&at) - shape.eval(&at)).abs() < 1e-12);
+        assert!((shape.simplify().grad(&at) - shape.grad(&at)).norm() < 1e-12);
+    }
+}
@@ -0,0 +1,299 @@
+use std::cell::RefCell;
+
+use nalgebra::Vector3;
+
+use crate::{Interval, SDFVolume, VolumetricFunc};
+
+use super::{
+    node::{BinaryOp, NodeId, SDFNode, UnaryOp},
+    noise::{perlin, perlin_bound},
+    SDFExpression, SDFGradient,
+};
+
+type Register = u32;
+
+#[derive(Clone)]
+enum Instruction {
+    Unary(Register, UnaryOp, Register),
+    Binary(Register, BinaryOp, Register, Register),
+    Select {
+        out: Register,
+        left: Register,
+        right: Register,
+        true_val: Register,
+        false_val: Register,
+    },
+    Noise {
+        out: Register,
+        seed: u32,
+        order: [u8; 3],
+        point: [Register; 3],
+    },
+}
+
+// An SDFTape is an SDFExpression and its gradient compiled to a flat list of instructions.
+// Instructions read and write a small set of reusable registers, so evaluating a tape doesn't
+// walk the expression graph, lock or allocate.
+#[derive(Clone)]
+pub struct SDFTape {
+    instructions: Vec<Instruction>,
+
+    // Registers start with x, y and z, followed by every constant, followed by temporary values.
+    constants: Vec<f64>,
+    registers: usize,
+
+    // The instructions needed for the value come first, the rest are only needed for the gradient.
+    value_len: usize,
+    value: Register,
+    grad: [Register; 3],
+}
+
+thread_local! {
+    // Registers are reused between evaluations on the same thread.
+    static REGISTERS: RefCell<Vec<f64>> = const { RefCell::new(Vec::new()) };
+}
+
+impl SDFTape {
+    fn run<const N: usize>(
+        &self,
+        len: usize,
+        outputs: [Register; N],
+        at: &Vector3<f64>,
+    ) -> [f64; N] {
+        REGISTERS.with_borrow_mut(|regs| {
+            if regs.len() < self.registers {
+                regs.resize(self.registers, 0.0);
+            }
+
+            regs[..3].copy_from_slice(at.as_slice());
+            regs[3..3 + self.constants.len()].copy_from_slice(&self.constants);
+
+            for inst in &self.instructions[..len] {
+                match *inst {
+                    Instruction::Unary(out, op, a) => {
+                        regs[out as usize] = op.apply(regs[a as usize])
+                    }
+                    Instruction::Binary(out, op, a, b) => {
+                        regs[out as usize] = op.apply(regs[a as usize], regs[b as usize])
+                    }
+                    Instruction::Select {
+                        out,
+                        left,
+                        right,
+                        true_val,
+                        false_val,
+                    } => {
+                        regs[out as usize] = if regs[left as usize] > regs[right as usize] {
+                            regs[true_val as usize]
+                        } else {
+                            regs[false_val as usize]
+                        }
+                    }
+                    Instruction::Noise {
+                        out,
+                        seed,
+                        order,
+                        point,
+                    } => regs[out as usize] = perlin(seed, order, point.map(|r| regs[r as usize])),
+                }
+            }
+
+            outputs.map(|r| regs[r as usize])
+        })
+    }
+}
+
+impl VolumetricFunc for SDFTape {
+    fn eval(&self, at: &Vector3<f64>) -> f64 {
+        let [val] = self.run(self.value_len, [self.value], at);
+        val
+    }
+
+    fn grad(&self, at: &Vector3<f64>) -> Vector3<f64> {
+        self.run(self.instructions.len(), self.grad, at).into()
+    }
+
+    fn eval_interval(&self, volume: &SDFVolume) -> Option<Interval> {
+        let mut regs = vec![Interval::point(0.0); self.registers];
+        regs[..3].copy_from_slice(&volume.intervals());
+        for (reg, c) in regs[3..].iter_mut().zip(&self.constants) {
+            *reg = Interval::point(*c);
+        }
+
+        for inst in &self.instructions[..self.value_len] {
+            match *inst {
+                Instruction::Unary(out, op, a) => {
+                    regs[out as usize] = op.apply_interval(regs[a as usize])
+                }
+                Instruction::Binary(out, BinaryOp::Mul, a, b) if a == b => {
+                    regs[out as usize] = regs[a as usize].square()
+                }
+                Instruction::Binary(out, op, a, b) => {
+                    regs[out as usize] = op.apply_interval(regs[a as usize], regs[b as usize])
+                }
+                Instruction::Select {
+                    out,
+                    left,
+                    right,
+                    true_val,
+                    false_val,
+                } => {
+                    regs[out as usize] = Interval::select(
+                        regs[left as usize],
+                        regs[right as usize],
+                        regs[true_val as usize],
+                        regs[false_val as usize],
+                    )
+                }
+                Instruction::Noise { out, order, .. } => {
+                    let bound = perlin_bound(order);
+                    regs[out as usize] = Interval::new(-bound, bound)
+                }
+            }
+        }
+
+        Some(regs[self.value as usize])
+    }
+}
+
+impl SDFExpression {
+    // Compiles this expression and its gradient into an SDFTape for faster evaluation.
+    pub fn compile(&self) -> SDFTape {
+        let SDFGradient {
+            graph,
+            value,
+            roots,
+        } = self.derive_grad();
+        let outputs = [value, roots[0], roots[1], roots[2]];
+
+        // Only nodes the outputs depend on are compiled.
+        let needed = graph.reachable_from(outputs);
+        let last = needed.len() - 1;
+
+        // Nodes that only depend on constants are evaluated now and stored with the other constants.
+        let mut constants = Vec::new();
+        let mut const_vals = vec![None; last + 1];
+        let mut registers = vec![0; last + 1];
+        for i in (0..=last).filter(|i| needed[*i]) {
+            let node = graph.node(NodeId(i));
+            let operands = node.operands();
+            if matches!(node, SDFNode::Dim(_))
+                || !operands.iter().all(|o| const_vals[o.0].is_some())
+            {
+                continue;
+            }
+
+            let val = node.eval(&[0.0; 3], |id| const_vals[id.0].unwrap());
+            const_vals[i] = Some(val);
+            registers[i] = (3 + constants.len()) as Register;
+            constants.push(val);
+        }
+
+        // The last node reading each node, after which its register can be reused.
+        // Outputs are never released.
+        let mut last_use = vec![0; last + 1];
+        for i in (0..=last).filter(|i| needed[*i]) {
+            for operand in graph.node(NodeId(i)).operands() {
+                last_use[operand.0] = i;
+            }
+        }
+        for root in outputs {
+            last_use[root.0] = usize::MAX;
+        }
+
+        let first_temporary = (3 + constants.len()) as Register;
+        let mut free = Vec::<Register>::new();
+        let mut count = first_temporary;
+        let mut instructions = Vec::new();
+        let mut value_len = 0;
+
+        for i in (0..=last).filter(|i| needed[*i]) {
+            let node = graph.node(NodeId(i));
+            if const_vals[i].is_some() {
+                continue;
+            }
+            if let SDFNode::Dim(d) = node {
+                registers[i] = *d as Register;
+                continue;
+            }
+
+            // Operands are read before the result is written, so their registers can be reused immediately.
+            for operand in node.operands() {
+                if last_use[operand.0] == i {
+                    last_use[operand.0] = 0;
+                    if registers[operand.0] >= first_temporary {
+                        free.push(registers[operand.0]);
+                    }
+                }
+            }
+            let out = free.pop().unwrap_or_else(|| {
+                count += 1;
+                count - 1
+            });
+            registers[i] = out;
+
+            let reg = |id: &NodeId| registers[id.0];
+            instructions.push(match node {
+                SDFNode::Unary(op, a) => Instruction::Unary(out, *op, reg(a)),
+                SDFNode::Binary(op, a, b) => Instruction::Binary(out, *op, reg(a), reg(b)),
+                SDFNode::GT {
+                    left,
+                    right,
+                    true_val,
+                    false_val,
+                } => Instruction::Select {
+                    out,
+                    left: reg(left),
+                    right: reg(right),
+                    true_val: reg(true_val),
+                    false_val: reg(false_val),
+                },
+                SDFNode::Noise { seed, order, point } => Instruction::Noise {
+                    out,
+                    seed: *seed,
+                    order: *order,
+                    point: point.map(|p| reg(&p)),
+                },
+                SDFNode::Const(_) | SDFNode::Dim(_) => unreachable!(),
+            });
+
+            if i <= value.0 {
+                value_len = instructions.len();
+            }
+        }
+
+        let [value, x, y, z] = outputs.map(|r| registers[r.0]);
+        SDFTape {
+            instructions,
+            constants,
+            registers: count as usize,
+            value_len,
+            value,
+            grad: [x, y, z],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+
+    use crate::{SDFExpression, VolumetricFunc};
+
+    #[test]
+    fn tape_matches_expression() {
+        let a = SDFExpression::rounded_cuboid(Vector3::zeros(), Vector3::new(1.0, 2.0, 0.5), 0.2);
+        let b = SDFExpression::torus(Vector3::new(0.5, 0.0, 0.0), 1.5, 0.25);
+        let expr = SDFExpression::smooth_min(a, b, 0.3).displace(0.05, 3.0, 1);
+        let tape = expr.compile();
+
+        for at in [
+            Vector3::new(0.1, 0.2, 0.3),
+            Vector3::new(2.0, -1.0, 0.5),
+            Vector3::new(-0.7, 1.9, -0.2),
+        ] {
+            assert_eq!(tape.eval(&at), expr.eval(&at));
+            assert_eq!(tape.grad(&at), expr.grad(&at));
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use nalgebra::{Matrix4, UnitQuaternion, Vector3};
+
+use super::SDFExpression;
+
+// Transformations are applied by substituting the transformed position for each dimension,
+// so the transformed expression is evaluated at the inverse transformed position.
+impl SDFExpression {
+    // Moves the surface by offset.
+    pub fn translate(self, offset: Vector3<f64>) -> Self {
+        self.transform(Matrix4::new_translation(&offset))
+    }
+
+    // Rotates the surface about the origin.
+    pub fn rotate(self, rotation: UnitQuaternion<f64>) -> Self {
+        self.transform(rotation.to_homogeneous())
+    }
+
+    // Scales the surface about the origin.
+    // The result is scaled too so it remains a distance.
+    // Panics unless factor is positive, since other factors can't be inverted or flip the sign.
+    pub fn scale(self, factor: f64) -> Self {
+        assert!(factor > 0.0, "Scale factors must be positive.");
+        self.transform(Matrix4::new_scaling(factor)) * factor.into()
+    }
+
+    // Scales the surface about the origin by a different factor in each dimension.
+    // Distances are no longer exact, the result is scaled by the smallest factor so it
+    // never overestimates the distance to the surface.
+    // Panics unless every factor is positive.
+    pub fn scale_non_uniform(self, factors: Vector3<f64>) -> Self {
+        assert!(factors.min() > 0.0, "Scale factors must be positive.");
+        self.transform(Matrix4::new_nonuniform_scaling(&factors)) * factors.min().into()
+    }
+
+    // Applies an affine transformation given as a homogeneous matrix, an Isometry3 or an Affine3.
+    // The result is only a distance if the transformation is an isometry.
+    // Panics if the transformation isn't invertible.
+    pub fn transform<T>(self, transform: T) -> Self
+    where
+        T: Into<Matrix4<f64>>,
+    {
+        let inverse = transform
+            .into()
+            .try_inverse()
+            .expect("Transformation is not invertible.");
+
+        let dims = [0, 1, 2].map(|row| {
+            let r = inverse.row(row);
+            Self::from(r[0]) * Self::x()
+                + Self::from(r[1]) * Self::y()
+                + Self::from(r[2]) * Self::z()
+                + r[3].into()
+        });
+
+        self.substitute(dims)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{Isometry3, UnitQuaternion, Vector3};
+
+    use crate::{SDFExpression, VolumetricFunc};
+
+    #[test]
+    fn transform_matches_inverse_position() {
+        let shape = SDFExpression::cuboid(Vector3::zeros(), Vector3::new(1.0, 2.0, 3.0));
+        let iso = Isometry3::new(Vector3::new(1.0, -2.0, 0.5), Vector3::new(0.3, 0.2, -0.7));
+        let moved = shape
+            .clone()
+            .rotate(UnitQuaternion::from_scaled_axis(Vector3::new(
+                0.3, 0.2, -0.7,
+            )))
+            .translate(Vector3::new(1.0, -2.0, 0.5));
+        let transformed = shape.clone().transform(iso);
+
+        for at in [
+            Vector3::new(0.2, 0.4, -1.0),
+            Vector3::new(3.0, -1.0, 2.0),
+            Vector3::new(-2.0, 0.0, 0.0),
+        ] {
+            let expected =
+                shape.eval(&iso.inverse_transform_vector(&(at - iso.translation.vector)));
+            assert!((moved.eval(&at) - expected).abs() < 1e-9);
+            assert!((transformed.eval(&at) - expected).abs() < 1e-9);
+
+            let expected_grad = iso.transform_vector(
+                &shape.grad(&iso.inverse_transform_vector(&(at - iso.translation.vector))),
+            );
+            assert!((transformed.grad(&at) - expected_grad).norm() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn scaling_keeps_bounded_distances() {
+        let sphere = SDFExpression::sphere(Vector3::zeros(), 1.0);
+
+        // A uniform scale stays exact.
+        let scaled = sphere.clone().scale(2.0);
+        let at = Vector3::new(0.0, 3.0, 4.0);
+        assert!((scaled.eval(&at) - 3.0).abs() < 1e-12);
+        assert!((scaled.grad(&at) - at / 5.0).norm() < 1e-12);
+
+        // Stretching along x gives the ellipsoid distance scaled by the smallest factor.
+        let stretched = sphere.scale_non_uniform(Vector3::new(2.0, 1.0, 0.5));
+        for (at, dist, grad) in [
+            (
+                Vector3::new(3.0, 0.0, 0.0),
+                0.25,
+                Vector3::new(0.25, 0.0, 0.0),
+            ),
+            (
+                Vector3::new(0.0, 2.0, 0.0),
+                0.5,
+                Vector3::new(0.0, 0.5, 0.0),
+            ),
+            (
+                Vector3::new(0.0, 0.0, 1.0),
+                0.5,
+                Vector3::new(0.0, 0.0, 1.0),
+            ),
+        ] {
+            assert!((stretched.eval(&at) - dist).abs() < 1e-12, "{}", at);
+            assert!((stretched.grad(&at) - grad).norm() < 1e-12, "{}", at);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_scale_panics() {
+        let _ = SDFExpression::sphere(Vector3::zeros(), 1.0).scale(0.0);
+    }
+}
@@ -20,8 +20,11 @@ macro_rules! impl_find {
             cache: &mut EvaluationCache,
             pow: usize,
         ) -> SVector<f64, $Dim> {
+            let vert_coords = subdivide_coord(coord, pow);
+            cache.prefetch_grad(vert_coords.iter().map(|c| subspace.unproject_coord(c)));
+
             let mut quadric = SMatrix::<f64, { $Dim + 1 }, { $Dim + 1 }>::default();
-            for vert_coord in subdivide_coord(coord, pow) {
+            for vert_coord in vert_coords {
                 let real_pos = cache.volume.real_pos(&vert_coord.norm_pos(), subspace);
 
                 let coord3 = subspace.unproject_coord(&vert_coord);
@@ -51,7 +54,10 @@ macro_rules! impl_find {
     };
 }
 
-fn subdivide_coord<const N: usize>(coord: &PartitionCoord<N>, pow: usize) -> Vec<PartitionCoord<N>>
+pub(crate) fn subdivide_coord<const N: usize>(
+    coord: &PartitionCoord<N>,
+    pow: usize,
+) -> Vec<PartitionCoord<N>>
 where
     [(); 1 << N]:,
 {
@@ -10,9 +10,16 @@ pub struct SolverSettings {
     // A value of 0 will not spawn any additional threads.
     pub worker_threads: usize,
 
+    // The value of func on the extracted isosurface.
+    pub iso_level: f64,
+
     // Octree construction settings.
     pub min_octree_depth: usize,
     pub max_octree_depth: usize,
+    // Also subdivide cells whose interval bounds contain the iso level, for functions supporting
+    // eval_interval.
+    // This finds small features between cell corners at the cost of extra subdivision.
+    pub interval_subdivision: bool,
 
     // Dual positioning settings.
     pub dual_sample_subdivisions: usize,
@@ -26,8 +33,10 @@ impl Default for SolverSettings {
     fn default() -> Self {
         Self {
             worker_threads: 0,
+            iso_level: 0.0,
             min_octree_depth: 3,
             max_octree_depth: 4,
+            interval_subdivision: false,
             dual_sample_subdivisions: 2,
             max_vert_fitting_steps: 32,
             vert_fitting_error: f64::EPSILON,
@@ -35,7 +44,7 @@ impl Default for SolverSettings {
     }
 }
 
-// find_isosurface returns a mesh approximating the isosurface at the 0 value of func.
+// find_isosurface returns a mesh approximating the isosurface where func equals settings.iso_level.
 // The implementation is based on the algorithm described in:
 // Isosurfaces Over Simplicial Partitions of Multiresolution Grids by Josiah Manson and Scott Schaefer.
 // min and max_depth control the minimum and maximum subdivision of space in each dimension.
@@ -43,12 +52,13 @@ pub fn find_isosurface<F>(func: &F, volume: &SDFVolume, settings: &SolverSetting
 where
     F: VolumetricFunc,
 {
-    let mut cache = EvaluationCache::new(func, volume);
+    let mut cache = EvaluationCache::new(func, volume, settings.iso_level);
 
     let (volume_cells, face_cells, edge_cells) = build_cell_trees(
         &mut cache,
         settings.min_octree_depth,
         settings.max_octree_depth,
+        settings.interval_subdivision,
     );
 
     find_all_volume_duals(
@@ -115,4 +125,67 @@ mod tests {
             )
         }
     }
+
+    #[test]
+    fn interval_subdivision_finds_small_features() {
+        // This sphere lies entirely between the corners of the cells at min_octree_depth.
+        let sphere = SDFExpression::sphere(Vector3::new(1.2, 1.3, 1.1), 0.4);
+
+        let volume = SDFVolume {
+            base: Vector3::new(-5.0, -5.0, -5.0),
+            size: Vector3::new(10.0, 10.0, 10.0),
+        };
+
+        let mut settings = SolverSettings {
+            min_octree_depth: 1,
+            max_octree_depth: 5,
+            ..Default::default()
+        };
+        assert!(find_isosurface(&sphere, &volume, &settings).0.is_empty());
+
+        settings.interval_subdivision = true;
+        assert!(!find_isosurface(&sphere, &volume, &settings).0.is_empty());
+        assert!(!find_isosurface(&sphere.compile(), &volume, &settings)
+            .0
+            .is_empty());
+    }
+
+    #[test]
+    fn iso_level_selects_the_surface() {
+        let squared = SDFExpression::x() * SDFExpression::x()
+            + SDFExpression::y() * SDFExpression::y()
+            + SDFExpression::z() * SDFExpression::z();
+
+        let volume = SDFVolume {
+            base: Vector3::new(-5.0, -5.0, -5.0),
+            size: Vector3::new(10.0, 10.0, 10.0),
+        };
+
+        // The squared distance reaches 4 on the sphere of radius 2.
+        let mut settings = SolverSettings {
+            iso_level: 4.0,
+            ..Default::default()
+        };
+        let mesh = find_isosurface(&squared, &volume, &settings);
+        assert!(!mesh.0.is_empty());
+        for vert in mesh.0 {
+            assert!((vert.norm() - 2.0).abs() < 0.01, "{}", vert.norm());
+        }
+
+        // Interval subdivision looks for the level too, finding this sphere of radius 0.6.
+        let sphere = SDFExpression::sphere(Vector3::new(1.2, 1.3, 1.1), 0.4);
+        settings = SolverSettings {
+            iso_level: 0.2,
+            min_octree_depth: 1,
+            max_octree_depth: 5,
+            interval_subdivision: true,
+            ..Default::default()
+        };
+        let mesh = find_isosurface(&sphere, &volume, &settings);
+        assert!(!mesh.0.is_empty());
+        for vert in mesh.0 {
+            let dist = (vert - Vector3::new(1.2, 1.3, 1.1)).norm();
+            assert!((dist - 0.6).abs() < 0.01, "{}", dist);
+        }
+    }
 }
@@ -10,6 +10,11 @@ mod partition;
 mod simplex;
 mod subspace;
 
-pub use data::{sdf::SDFExpression, Dimension, SDFVolume, VolumetricFunc};
+pub use data::{
+    sdf::{ParseError, SDFExpression, SDFProfile, SDFTape},
+    AutoDiff, CSGOp, ClosureFunc, Dimension, Dual, Endian, GridVolume, Interpolation, Interval,
+    Kernel, LoadError, MeshSDF, Metaball, Metaballs, PointCloudSDF, RawLayout, SDFVolume, Scalar,
+    ScalarFunc, VolumetricFunc, VoxelType, CSG,
+};
 pub use isosurface::{find_isosurface, SolverSettings};
 pub use mesh::MeshBuffers;
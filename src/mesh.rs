@@ -133,16 +133,17 @@ impl<'a> FaceVert<'a> {
         let mut ov = self.o.eval(cache);
         let mut ip = self.i.pos(cache);
         let mut op = self.o.pos(cache);
+        let level = cache.iso_level;
 
         let mut cp = ip;
         for _ in 0..max_fitting_steps {
-            let t = (-iv / (ov - iv)).clamp(0.0, 1.0);
+            let t = ((level - iv) / (ov - iv)).clamp(0.0, 1.0);
             cp = ip * (1.0 - t) + op * t;
             let cv = cache.eval_real(&cp);
 
-            if cv.abs() <= fitting_error {
+            if (cv - level).abs() <= fitting_error {
                 break;
-            } else if cv < 0.0 {
+            } else if cv < level {
                 ip = cp;
                 iv = cv;
             } else {
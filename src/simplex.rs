@@ -59,7 +59,7 @@ impl<'a> SimplexVert<'a> {
     }
 
     pub(crate) fn inside(&self, cache: &mut EvaluationCache) -> bool {
-        self.eval(cache) < 0.0
+        self.eval(cache) < cache.iso_level
     }
 }
 